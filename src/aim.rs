@@ -0,0 +1,171 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    input::ActionState,
+    movement::Facing,
+    player::Player,
+    schedule::{AppState, InGame},
+};
+
+/// How far from the player the reticle sits when aiming with a stick
+const STICK_RETICLE_DISTANCE: f32 = 24.;
+const RETICLE_SIZE: f32 = 7.;
+const RETICLE_THICKNESS: f32 = 1.;
+const RETICLE_COLOR: Color = Color::rgba(1., 1., 1., 0.8);
+
+pub struct AimPlugin;
+
+impl Plugin for AimPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AimSettings>()
+            .init_resource::<Aim>()
+            .add_systems(OnEnter(AppState::InGame), spawn_reticle)
+            .add_systems(OnExit(AppState::InGame), despawn_reticle)
+            .add_systems(
+                Update,
+                (aim_player, update_reticle)
+                    .chain()
+                    .in_set(InGame::UserInput),
+            );
+    }
+}
+
+/// Lets the mouse or right stick control where the player faces, and so where directional
+/// weapons fire, instead of the direction they last moved in.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct AimSettings {
+    pub enabled: bool,
+}
+
+/// The device the player last aimed with. The cursor stays wherever it was left, so it only
+/// takes over again once it moves.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum AimDevice {
+    #[default]
+    Mouse,
+    Stick,
+}
+
+/// Where the reticle goes this frame: on the cursor, or in front of the player when aiming
+/// with a stick. `None` hides it.
+#[derive(Resource, Debug, Default)]
+struct Aim {
+    device: AimDevice,
+    target: Option<Vec2>,
+}
+
+#[derive(Component, Debug, Default)]
+pub struct Reticle;
+
+fn spawn_reticle(mut commands: Commands) {
+    let bar = |size: Vec2| SpriteBundle {
+        sprite: Sprite {
+            color: RETICLE_COLOR,
+            custom_size: Some(size),
+            ..default()
+        },
+        ..default()
+    };
+    commands
+        .spawn((
+            Reticle,
+            SpatialBundle {
+                visibility: Visibility::Hidden,
+                transform: Transform::from_xyz(0., 0., 200.),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(bar(Vec2::new(RETICLE_SIZE, RETICLE_THICKNESS)));
+            parent.spawn(bar(Vec2::new(RETICLE_THICKNESS, RETICLE_SIZE)));
+        });
+}
+
+fn despawn_reticle(mut commands: Commands, query: Query<Entity, With<Reticle>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// The world position under the cursor, if it's over the camera's viewport
+fn cursor_world_position(
+    window: &Window,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    let mut cursor = window.cursor_position()?;
+    // The viewport is letterboxed, and the cursor is relative to the whole window
+    if let Some(viewport) = camera.logical_viewport_rect() {
+        if !viewport.contains(cursor) {
+            return None;
+        }
+        cursor -= viewport.min;
+    }
+    camera.viewport_to_world_2d(camera_transform, cursor)
+}
+
+fn aim_player(
+    settings: Res<AimSettings>,
+    actions: Res<ActionState>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mut aim: ResMut<Aim>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    mut player: Query<(&Transform, &mut Facing), With<Player>>,
+) {
+    let mouse_moved = cursor_moved.read().count() > 0;
+    aim.target = None;
+    if !settings.enabled {
+        return;
+    }
+    let Ok((transform, mut facing)) = player.get_single_mut() else {
+        return;
+    };
+    let player_position = transform.translation.truncate();
+
+    let stick = actions.aim();
+    if stick != Vec2::ZERO {
+        aim.device = AimDevice::Stick;
+    } else if mouse_moved {
+        aim.device = AimDevice::Mouse;
+    }
+
+    let direction = match aim.device {
+        AimDevice::Stick => stick,
+        AimDevice::Mouse => {
+            let (Ok(window), Ok((camera, camera_transform))) =
+                (window.get_single(), camera.get_single())
+            else {
+                return;
+            };
+            let Some(target) = cursor_world_position(window, camera, camera_transform) else {
+                return;
+            };
+            aim.target = Some(target);
+            target - player_position
+        }
+    };
+    // A centered stick, or the cursor right on the player, keeps the last direction
+    if direction.length_squared() > f32::EPSILON {
+        facing.value = direction.normalize().extend(0.);
+    }
+    if aim.device == AimDevice::Stick {
+        aim.target = Some(player_position + facing.value.truncate() * STICK_RETICLE_DISTANCE);
+    }
+}
+
+fn update_reticle(
+    aim: Res<Aim>,
+    mut reticle: Query<(&mut Transform, &mut Visibility), With<Reticle>>,
+) {
+    let Ok((mut transform, mut visibility)) = reticle.get_single_mut() else {
+        return;
+    };
+    match aim.target {
+        Some(target) => {
+            transform.translation = target.extend(transform.translation.z);
+            *visibility = Visibility::Visible;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
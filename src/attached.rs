@@ -0,0 +1,326 @@
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle, utils::HashMap};
+
+use crate::{
+    asset_loader::SpriteAssets,
+    collision::Collider,
+    damage::{CritStats, DamagePipeline, DamageRequest, DamageRoll, DamageSource, DamageType},
+    enemies::Enemy,
+    movement::rotation_from_direction,
+    player::{add_or_level_up, LeveledWeapon, Player, WeaponType},
+    schedule::InGame,
+};
+
+const BLADE_SIZE: Vec2 = Vec2::new(6., 14.);
+/// Radians per second
+const BLADE_ANGULAR_SPEED: f32 = 3.;
+/// How long an enemy is safe from a blade after it was hit by it
+const BLADE_HIT_INTERVAL: f32 = 0.5;
+const BLADE_VARIANCE: f32 = 0.2;
+
+/// Seconds between aura pulses
+const AURA_INTERVAL: f32 = 1.;
+const AURA_COLOR: Color = Color::rgba(1., 0.95, 0.6, 0.15);
+
+pub struct AttachedWeaponsPlugin;
+
+impl Plugin for AttachedWeaponsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                add_or_level_up::<OrbitingBlades>,
+                add_or_level_up::<Aura>,
+                apply_deferred,
+                sync_blades,
+                sync_aura,
+            )
+                .chain()
+                .in_set(InGame::EntityUpdates),
+        )
+        .add_systems(Update, orbit_blades.in_set(InGame::UserInput))
+        .add_systems(
+            Update,
+            (blade_hits, aura_pulses).in_set(DamagePipeline::Request),
+        );
+    }
+}
+
+/// Blades circling the player, hitting every enemy they pass through.
+#[derive(Component, Debug, Clone)]
+pub struct OrbitingBlades {
+    pub level: u32,
+    angle: f32,
+}
+
+impl OrbitingBlades {
+    pub fn count(&self) -> usize {
+        (self.level + 1).min(6) as usize
+    }
+
+    pub fn radius(&self) -> f32 {
+        20. + 4. * (self.level - 1) as f32
+    }
+
+    pub fn damage(&self) -> u32 {
+        2 + self.level
+    }
+}
+
+/// A ring around the player that damages every enemy inside it on an interval.
+#[derive(Component, Debug, Clone)]
+pub struct Aura {
+    pub level: u32,
+    timer: Timer,
+}
+
+impl Aura {
+    pub fn cooldown(&self) -> &Timer {
+        &self.timer
+    }
+
+    pub fn radius(&self) -> f32 {
+        24. + 6. * (self.level - 1) as f32
+    }
+
+    pub fn damage(&self) -> u32 {
+        1 + self.level / 2
+    }
+}
+
+impl LeveledWeapon for OrbitingBlades {
+    const KIND: WeaponType = WeaponType::OrbitingBlades;
+
+    fn new() -> Self {
+        Self {
+            level: 1,
+            angle: 0.,
+        }
+    }
+
+    fn level_mut(&mut self) -> &mut u32 {
+        &mut self.level
+    }
+}
+
+impl LeveledWeapon for Aura {
+    const KIND: WeaponType = WeaponType::HolyAura;
+
+    fn new() -> Self {
+        Self {
+            level: 1,
+            timer: Timer::from_seconds(AURA_INTERVAL, TimerMode::Repeating),
+        }
+    }
+
+    fn level_mut(&mut self) -> &mut u32 {
+        &mut self.level
+    }
+}
+
+/// One of the player's [`OrbitingBlades`], spawned as a child of the player.
+#[derive(Component, Debug)]
+struct Blade {
+    index: usize,
+    level: u32,
+    /// Enemies this blade hit recently, with the time until it can hit them again
+    recent_hits: HashMap<Entity, Timer>,
+}
+
+/// The visible, colliding area of the player's [`Aura`], spawned as a child of the player.
+#[derive(Component, Debug)]
+struct AuraField {
+    level: u32,
+}
+
+/// Respawns the blades whenever the weapon levels up
+fn sync_blades(
+    mut commands: Commands,
+    players: Query<(Entity, &OrbitingBlades, &CritStats), With<Player>>,
+    blades: Query<(Entity, &Parent, &Blade)>,
+    sprite_assets: Res<SpriteAssets>,
+) {
+    for (player, orbit, crit) in players.iter() {
+        let current: Vec<_> = blades
+            .iter()
+            .filter(|(_, parent, _)| parent.get() == player)
+            .collect();
+        if current.len() == orbit.count() && current.iter().all(|(_, _, b)| b.level == orbit.level)
+        {
+            continue;
+        }
+
+        for (blade, _, _) in current {
+            commands.entity(blade).despawn_recursive();
+        }
+        commands.entity(player).with_children(|parent| {
+            for index in 0..orbit.count() {
+                parent.spawn((
+                    Blade {
+                        index,
+                        level: orbit.level,
+                        recent_hits: HashMap::new(),
+                    },
+                    SpriteSheetBundle {
+                        texture_atlas: sprite_assets.tiles.clone(),
+                        sprite: TextureAtlasSprite::new(WeaponType::OrbitingBlades.sprite_index()),
+                        ..default()
+                    },
+                    Collider::new(BLADE_SIZE),
+                    DamageSource {
+                        attacker: Some(player),
+                        weapon: Some(WeaponType::OrbitingBlades),
+                    },
+                    DamageRoll {
+                        crit: *crit,
+                        variance: BLADE_VARIANCE,
+                    },
+                ));
+            }
+        });
+    }
+}
+
+/// Spawns the aura's field, and resizes it whenever the weapon levels up
+fn sync_aura(
+    mut commands: Commands,
+    players: Query<(Entity, &Aura), With<Player>>,
+    mut fields: Query<(&Parent, &mut AuraField, &mut Collider, &mut Transform)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (player, aura) in players.iter() {
+        let radius = aura.radius();
+        let existing = fields
+            .iter_mut()
+            .find(|(parent, _, _, _)| parent.get() == player);
+        match existing {
+            Some((_, mut field, mut collider, mut transform)) => {
+                if field.level != aura.level {
+                    field.level = aura.level;
+                    collider.size = Vec2::splat(radius * 2.);
+                    transform.scale = Vec3::splat(radius);
+                }
+            }
+            None => {
+                commands.entity(player).with_children(|parent| {
+                    parent.spawn((
+                        AuraField { level: aura.level },
+                        MaterialMesh2dBundle {
+                            mesh: meshes.add(shape::Circle::new(1.).into()).into(),
+                            material: materials.add(ColorMaterial::from(AURA_COLOR)),
+                            // Drawn under the player, scaled up to the aura's radius
+                            transform: Transform::from_xyz(0., 0., -1.)
+                                .with_scale(Vec3::splat(radius)),
+                            ..default()
+                        },
+                        Collider::new(Vec2::splat(radius * 2.)),
+                    ));
+                });
+            }
+        }
+    }
+}
+
+/// Spins the blades around the player, each pointing away from it
+fn orbit_blades(
+    mut players: Query<&mut OrbitingBlades, With<Player>>,
+    mut blades: Query<(&Parent, &Blade, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (parent, blade, mut transform) in blades.iter_mut() {
+        let Ok(orbit) = players.get(parent.get()) else {
+            continue;
+        };
+        let angle = orbit.angle + std::f32::consts::TAU * blade.index as f32 / orbit.count() as f32;
+        let direction = Vec2::from_angle(angle).extend(0.);
+        transform.translation = (direction * orbit.radius()).truncate().extend(1.);
+        transform.rotation = rotation_from_direction(direction);
+    }
+
+    for mut orbit in players.iter_mut() {
+        orbit.angle =
+            (orbit.angle + BLADE_ANGULAR_SPEED * time.delta_seconds()) % std::f32::consts::TAU;
+    }
+}
+
+/// Blades hit every enemy they touch, each enemy at most once per [`BLADE_HIT_INTERVAL`]
+fn blade_hits(
+    mut blades: Query<(&Parent, &mut Blade, &Collider, &DamageSource, &DamageRoll)>,
+    players: Query<&OrbitingBlades>,
+    enemies: Query<(), With<Enemy>>,
+    mut requests: EventWriter<DamageRequest>,
+    time: Res<Time>,
+) {
+    for (parent, mut blade, collider, source, roll) in blades.iter_mut() {
+        let Ok(orbit) = players.get(parent.get()) else {
+            continue;
+        };
+
+        blade
+            .recent_hits
+            .retain(|_, timer| !timer.tick(time.delta()).finished());
+
+        for enemy in collider.collisions.iter() {
+            if !enemies.contains(*enemy) || blade.recent_hits.contains_key(enemy) {
+                continue;
+            }
+            blade.recent_hits.insert(
+                *enemy,
+                Timer::from_seconds(BLADE_HIT_INTERVAL, TimerMode::Once),
+            );
+            requests.send(DamageRequest {
+                target: *enemy,
+                amount: orbit.damage(),
+                damage_type: DamageType::Physical,
+                source: *source,
+                roll: *roll,
+            });
+        }
+    }
+}
+
+/// Pulses damage into every enemy within the aura's radius
+fn aura_pulses(
+    mut players: Query<(Entity, &mut Aura, &Transform, &CritStats, &Children), With<Player>>,
+    fields: Query<&Collider, With<AuraField>>,
+    enemies: Query<&Transform, With<Enemy>>,
+    mut requests: EventWriter<DamageRequest>,
+    time: Res<Time>,
+) {
+    for (player, mut aura, transform, crit, children) in players.iter_mut() {
+        if !aura.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let Some(field) = children.iter().find_map(|child| fields.get(*child).ok()) else {
+            continue;
+        };
+
+        // The collider is square, only enemies inside the circle are hit
+        let radius = aura.radius();
+        for enemy in field.collisions.iter() {
+            let Ok(enemy_transform) = enemies.get(*enemy) else {
+                continue;
+            };
+            let distance = enemy_transform
+                .translation
+                .truncate()
+                .distance(transform.translation.truncate());
+            if distance > radius {
+                continue;
+            }
+            requests.send(DamageRequest {
+                target: *enemy,
+                amount: aura.damage(),
+                damage_type: DamageType::Holy,
+                source: DamageSource {
+                    attacker: Some(player),
+                    weapon: Some(WeaponType::HolyAura),
+                },
+                roll: DamageRoll {
+                    crit: *crit,
+                    variance: 0.,
+                },
+            });
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset_loader::SpriteAssets,
+    collision::{segment_entry, Collider},
+    damage::{CritStats, DamagePipeline, DamageRequest, DamageRoll, DamageSource, DamageType},
+    enemies::Enemy,
+    levels::Wall,
+    movement::{rotation_from_direction, Facing},
+    player::{add_or_level_up, LeveledWeapon, Player, WeaponType},
+    schedule::InGame,
+};
+
+const BEAM_COOLDOWN: f32 = 2.0;
+/// How far a beam reaches when there's no wall in the way
+const BEAM_RANGE: f32 = 160.;
+/// Seconds the beam stays on screen after firing
+const BEAM_DURATION: f32 = 0.25;
+/// Distance between the sprites drawing a beam
+const BEAM_SEGMENT_LENGTH: f32 = 12.;
+const BEAM_COLOR: Color = Color::rgb(0.6, 0.85, 1.0);
+const BEAM_VARIANCE: f32 = 0.1;
+
+pub struct BeamPlugin;
+
+impl Plugin for BeamPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (add_or_level_up::<LightningRod>, fade_beams).in_set(InGame::EntityUpdates),
+        )
+        .add_systems(Update, fire_beams.in_set(DamagePipeline::Request));
+    }
+}
+
+/// Fires a beam along [`Facing`] that stops at the first wall and hits every enemy on the way.
+#[derive(Component, Debug, Clone)]
+pub struct LightningRod {
+    pub level: u32,
+    cooldown: Timer,
+}
+
+impl LightningRod {
+    pub fn cooldown(&self) -> &Timer {
+        &self.cooldown
+    }
+
+    pub fn width(&self) -> f32 {
+        4. + self.level as f32
+    }
+
+    pub fn damage(&self) -> u32 {
+        4 + 2 * self.level
+    }
+}
+
+impl LeveledWeapon for LightningRod {
+    const KIND: WeaponType = WeaponType::LightningRod;
+
+    fn new() -> Self {
+        Self {
+            level: 1,
+            cooldown: Timer::from_seconds(BEAM_COOLDOWN, TimerMode::Repeating),
+        }
+    }
+
+    fn level_mut(&mut self) -> &mut u32 {
+        &mut self.level
+    }
+}
+
+/// The sprites drawing a beam that was just fired, despawned once they fade out.
+#[derive(Component, Debug)]
+struct BeamDisplay(Timer);
+
+fn fire_beams(
+    mut commands: Commands,
+    mut players: Query<(Entity, &mut LightningRod, &Transform, &Facing, &CritStats), With<Player>>,
+    walls: Query<(&Transform, &Collider), With<Wall>>,
+    enemies: Query<(Entity, &Transform, &Collider), With<Enemy>>,
+    mut requests: EventWriter<DamageRequest>,
+    sprite_assets: Res<SpriteAssets>,
+    time: Res<Time>,
+) {
+    for (player, mut rod, transform, facing, crit) in players.iter_mut() {
+        if !rod.cooldown.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let direction = facing.value.truncate().normalize_or_zero();
+        let start = transform.translation.truncate();
+        let furthest = start + direction * BEAM_RANGE;
+        let length = walls
+            .iter()
+            .filter_map(|(wall_transform, collider)| {
+                segment_entry(start, furthest, collider.to_rect_at(wall_transform))
+            })
+            .fold(1.0f32, f32::min)
+            * BEAM_RANGE;
+        let end = start + direction * length;
+
+        // Widening each enemy by the beam's width is the same as testing a thick beam
+        let width = rod.width();
+        for (enemy, enemy_transform, collider) in enemies.iter() {
+            let rect = collider.to_rect_at(enemy_transform);
+            let rect = Rect::from_center_size(rect.center(), rect.size() + Vec2::splat(width));
+            if segment_entry(start, end, rect).is_none() {
+                continue;
+            }
+            requests.send(DamageRequest {
+                target: enemy,
+                amount: rod.damage(),
+                damage_type: DamageType::Lightning,
+                source: DamageSource {
+                    attacker: Some(player),
+                    weapon: Some(WeaponType::LightningRod),
+                },
+                roll: DamageRoll {
+                    crit: *crit,
+                    variance: BEAM_VARIANCE,
+                },
+            });
+        }
+
+        let rotation = rotation_from_direction(direction.extend(0.));
+        let segments = (length / BEAM_SEGMENT_LENGTH).ceil() as usize;
+        commands
+            .spawn((
+                BeamDisplay(Timer::from_seconds(BEAM_DURATION, TimerMode::Once)),
+                SpatialBundle::from_transform(Transform::from_translation(start.extend(150.))),
+            ))
+            .with_children(|parent| {
+                for segment in 0..segments {
+                    let distance = (segment as f32 + 0.5) * BEAM_SEGMENT_LENGTH;
+                    parent.spawn(SpriteSheetBundle {
+                        texture_atlas: sprite_assets.tiles.clone(),
+                        sprite: TextureAtlasSprite {
+                            index: WeaponType::LightningRod.sprite_index(),
+                            color: BEAM_COLOR,
+                            custom_size: Some(Vec2::new(width, BEAM_SEGMENT_LENGTH)),
+                            ..default()
+                        },
+                        transform: Transform::from_translation((direction * distance).extend(0.))
+                            .with_rotation(rotation),
+                        ..default()
+                    });
+                }
+            });
+    }
+}
+
+fn fade_beams(
+    mut commands: Commands,
+    mut beams: Query<(Entity, &mut BeamDisplay, &Children)>,
+    mut sprites: Query<&mut TextureAtlasSprite>,
+    time: Res<Time>,
+) {
+    for (entity, mut beam, children) in beams.iter_mut() {
+        if beam.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = 1.0 - beam.0.percent();
+        let mut iter = sprites.iter_many_mut(children);
+        while let Some(mut sprite) = iter.fetch_next() {
+            sprite.color.set_a(alpha);
+        }
+    }
+}
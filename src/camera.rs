@@ -1,61 +1,431 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, render::camera::Viewport, window::PrimaryWindow};
+use bevy_ecs_ldtk::prelude::*;
+use rand::random;
 
-use crate::{player::Player, schedule::InGame};
+use crate::{
+    damage::{DamagePipeline, DeathEvent},
+    enemies::Enemy,
+    health::{DamageEvent, Health},
+    movement::Velocity,
+    player::Player,
+    schedule::{AppState, InGame},
+};
 
-// const CAMERA_SCALE: f32 = 0.75;
+/// Screen pixels per level pixel when the game starts
+const DEFAULT_ZOOM: u32 = 2;
+pub const MAX_ZOOM: u32 = 6;
+/// Window height the UI is laid out for, taller windows scale the UI up
+const UI_REFERENCE_HEIGHT: f32 = 600.;
+
+/// Largest camera offset in pixels, reached at full trauma
+const MAX_SHAKE_OFFSET: f32 = 6.0;
+/// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+const PLAYER_HIT_TRAUMA: f32 = 0.4;
+const BIG_ENEMY_DEATH_TRAUMA: f32 = 0.3;
+/// Enemies with at least this much maximum health shake the screen when they die
+const BIG_ENEMY_HEALTH: u32 = 20;
+/// Hits on the player and big enemy deaths briefly freeze the game
+const IMPACT_HIT_STOP: HitStop = HitStop {
+    duration: 0.05,
+    time_scale: 0.05,
+};
+/// Real seconds after a hit-stop starts during which new ones are ignored
+const HIT_STOP_COOLDOWN: f32 = 0.5;
 
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_camera)
-            .add_systems(Update, camera_follows_player.in_set(InGame::EntityUpdates));
+        app.add_event::<CameraShake>()
+            .add_event::<HitStop>()
+            .init_resource::<ScreenEffects>()
+            .init_resource::<CameraZoom>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(Update, (apply_pixel_scaling, scale_ui))
+            .add_systems(
+                Update,
+                shake_on_combat
+                    .after(DamagePipeline::Apply)
+                    .in_set(InGame::ProcessCombat),
+            )
+            .add_systems(
+                Update,
+                (
+                    remove_camera_shake,
+                    camera_follows_player,
+                    apply_camera_shake,
+                )
+                    .chain()
+                    .in_set(InGame::EntityUpdates),
+            )
+            .add_systems(Update, update_hit_stop.in_set(InGame::EntityUpdates))
+            .add_systems(OnExit(AppState::InGame), end_hit_stop);
+
+        #[cfg(debug_assertions)]
+        app.add_systems(Update, debug_zoom.before(apply_pixel_scaling));
+    }
+}
+
+/// How many screen pixels each level pixel covers. Always a whole number so tiles stay crisp.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct CameraZoom {
+    pub factor: u32,
+}
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        Self {
+            factor: DEFAULT_ZOOM,
+        }
+    }
+}
+
+impl CameraZoom {
+    pub fn zoom_in(&mut self) {
+        self.factor = (self.factor + 1).min(MAX_ZOOM);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.factor = self.factor.saturating_sub(1).max(1);
     }
 }
 
+/// Which camera effects the player wants to see.
+#[derive(Resource, Debug, Clone)]
+pub struct ScreenEffects {
+    /// Scales how far the camera shakes, 0.0 turns shaking off
+    pub screen_shake: f32,
+    pub hit_stop: bool,
+}
+
+impl Default for ScreenEffects {
+    fn default() -> Self {
+        Self {
+            screen_shake: 1.0,
+            hit_stop: true,
+        }
+    }
+}
+
+/// Adds `trauma` (0.0 - 1.0) to the camera. The camera shakes with the square of its trauma.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct CameraShake {
+    pub trauma: f32,
+}
+
+/// Slows the game down to `time_scale` for `duration` real seconds.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct HitStop {
+    pub duration: f32,
+    pub time_scale: f32,
+}
+
+/// The camera's current trauma and the offset it was moved by this frame.
+#[derive(Component, Debug, Default)]
+pub struct Shake {
+    trauma: f32,
+    offset: Vec3,
+}
+
+/// How the camera tracks the player.
+#[derive(Component, Debug, Clone)]
+pub struct CameraFollow {
+    /// How quickly the camera catches up, higher is snappier. Framerate independent.
+    pub smoothing: f32,
+    /// Half extents of the rectangle around the camera the player can move in freely
+    pub deadzone: Vec2,
+    /// Seconds of player velocity the camera looks ahead by
+    pub lookahead: f32,
+    pub max_lookahead: f32,
+    /// The smoothed position before pixel snapping
+    position: Option<Vec2>,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        Self {
+            smoothing: 8.0,
+            deadzone: Vec2::new(16., 12.),
+            lookahead: 0.4,
+            max_lookahead: 32.,
+            position: None,
+        }
+    }
+}
+
+/// Real time left in the current hit-stop, and since the last one started.
+#[derive(Debug, Default)]
+struct HitStopTimer {
+    remaining: Option<Timer>,
+    cooldown: Option<Timer>,
+}
+
 fn spawn_camera(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+    commands.spawn((
+        Camera2dBundle::default(),
+        CameraFollow::default(),
+        Shake::default(),
+    ));
+}
+
+/// Scales the camera by the zoom factor and letterboxes the viewport to a whole number of
+/// level pixels, so every level pixel lands on the same number of screen pixels.
+fn apply_pixel_scaling(
+    mut camera: Query<(&mut Camera, &mut OrthographicProjection)>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    zoom: Res<CameraZoom>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let Ok((mut camera, mut projection)) = camera.get_single_mut() else {
+        return;
+    };
+
+    let factor = zoom.factor.clamp(1, MAX_ZOOM);
+    // The projection works in logical pixels, the zoom factor in physical ones
+    let scale = window.scale_factor() as f32 / factor as f32;
+    if projection.scale != scale {
+        projection.scale = scale;
+    }
+
+    let window_size = UVec2::new(window.physical_width(), window.physical_height());
+    let physical_size = (window_size / factor) * factor;
+    let viewport = Viewport {
+        physical_position: (window_size - physical_size) / 2,
+        physical_size,
+        ..Default::default()
+    };
+    let unchanged = camera.viewport.as_ref().is_some_and(|current| {
+        current.physical_position == viewport.physical_position
+            && current.physical_size == viewport.physical_size
+    });
+    if !unchanged && physical_size.x > 0 && physical_size.y > 0 {
+        camera.viewport = Some(viewport);
+    }
+}
+
+/// Grows the UI with the window so text stays readable on large screens.
+fn scale_ui(window: Query<&Window, With<PrimaryWindow>>, mut ui_scale: ResMut<UiScale>) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let scale = (window.height() / UI_REFERENCE_HEIGHT).max(1.0) as f64;
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
+#[cfg(debug_assertions)]
+fn debug_zoom(
+    mut wheel: EventReader<bevy::input::mouse::MouseWheel>,
+    keys: Res<Input<KeyCode>>,
+    mut zoom: ResMut<CameraZoom>,
+) {
+    let scrolled: f32 = wheel.read().map(|event| event.y).sum();
+    if scrolled > 0. || keys.just_pressed(KeyCode::Equals) {
+        zoom.zoom_in();
+    } else if scrolled < 0. || keys.just_pressed(KeyCode::Minus) {
+        zoom.zoom_out();
+    }
+}
+
+fn shake_on_combat(
+    mut damage_events: EventReader<DamageEvent>,
+    mut deaths: EventReader<DeathEvent>,
+    mut shakes: EventWriter<CameraShake>,
+    mut hit_stops: EventWriter<HitStop>,
+    players: Query<(), With<Player>>,
+    enemies: Query<&Health, With<Enemy>>,
+) {
+    let mut impact = false;
+    for event in damage_events.read() {
+        if players.contains(event.receiver) && event.amount > 0 {
+            shakes.send(CameraShake {
+                trauma: PLAYER_HIT_TRAUMA,
+            });
+            impact = true;
+        }
+    }
+
+    for death in deaths.read() {
+        if enemies
+            .get(death.entity)
+            .is_ok_and(|health| health.total >= BIG_ENEMY_HEALTH)
+        {
+            shakes.send(CameraShake {
+                trauma: BIG_ENEMY_DEATH_TRAUMA,
+            });
+            impact = true;
+        }
+    }
+
+    if impact {
+        hit_stops.send(IMPACT_HIT_STOP);
+    }
+}
+
+/// Undoes last frame's shake so that following works from the camera's real position
+fn remove_camera_shake(mut camera: Query<(&mut Transform, &mut Shake), With<Camera>>) {
+    for (mut transform, mut shake) in camera.iter_mut() {
+        transform.translation -= shake.offset;
+        shake.offset = Vec3::ZERO;
+    }
+}
+
+fn apply_camera_shake(
+    mut camera: Query<(&mut Transform, &mut Shake), With<Camera>>,
+    mut events: EventReader<CameraShake>,
+    settings: Res<ScreenEffects>,
+    time: Res<Time>,
+) {
+    let added: f32 = events.read().map(|event| event.trauma).sum();
+    for (mut transform, mut shake) in camera.iter_mut() {
+        if settings.screen_shake <= 0. {
+            shake.trauma = 0.;
+            continue;
+        }
+
+        shake.trauma = (shake.trauma + added - TRAUMA_DECAY * time.delta_seconds()).clamp(0., 1.);
+        if shake.trauma <= 0. {
+            continue;
+        }
+
+        // Cosmetic only, so this deliberately doesn't draw from the run's RNG
+        let direction = Vec2::new(random::<f32>() * 2. - 1., random::<f32>() * 2. - 1.);
+        let offset = (direction * MAX_SHAKE_OFFSET * settings.screen_shake * shake.trauma.powi(2))
+            .round()
+            .extend(0.);
+        shake.offset = offset;
+        transform.translation += offset;
+    }
+}
+
+fn update_hit_stop(
+    mut events: EventReader<HitStop>,
+    mut hit_stop: Local<HitStopTimer>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+    settings: Res<ScreenEffects>,
+) {
+    if let Some(cooldown) = hit_stop.cooldown.as_mut() {
+        if cooldown.tick(real_time.delta()).finished() {
+            hit_stop.cooldown = None;
+        }
+    }
+
+    for event in events.read() {
+        // Back to back hit-stops would make busy fights stutter
+        if !settings.hit_stop || hit_stop.cooldown.is_some() {
+            continue;
+        }
+        hit_stop.remaining = Some(Timer::from_seconds(event.duration, TimerMode::Once));
+        hit_stop.cooldown = Some(Timer::from_seconds(HIT_STOP_COOLDOWN, TimerMode::Once));
+        virtual_time.set_relative_speed(event.time_scale);
+    }
+
+    let Some(timer) = hit_stop.remaining.as_mut() else {
+        return;
+    };
+    if timer.tick(real_time.delta()).finished() {
+        hit_stop.remaining = None;
+        virtual_time.set_relative_speed(1.0);
+    }
+}
+
+fn end_hit_stop(mut virtual_time: ResMut<Time<Virtual>>) {
+    virtual_time.set_relative_speed(1.0);
 }
 
 fn camera_follows_player(
-    player: Query<&Transform, With<Player>>,
-    mut camera: Query<(&mut Transform, &OrthographicProjection), (With<Camera>, Without<Player>)>,
-    // level_query: Query<(&Transform, &LevelIid), (Without<OrthographicProjection>, Without<Player>)>,
-    // level_selection: Res<LevelSelection>,
-    // ldtk_projects: Query<&Handle<LdtkProject>>,
-    // ldtk_project_assets: Res<Assets<LdtkProject>>,
+    player: Query<(&Transform, Option<&Velocity>), With<Player>>,
+    mut camera: Query<
+        (&mut Transform, &mut CameraFollow, &OrthographicProjection),
+        (With<Camera>, Without<Player>),
+    >,
+    level_query: Query<(&Transform, &LevelIid), (Without<OrthographicProjection>, Without<Player>)>,
+    level_selection: Res<LevelSelection>,
+    ldtk_projects: Query<&Handle<LdtkProject>>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+    time: Res<Time>,
 ) {
-    let Ok((mut camera_transform, _projection)) = camera.get_single_mut() else {
+    let Ok((mut camera_transform, mut follow, projection)) = camera.get_single_mut() else {
         return;
     };
 
-    let Ok(player_transform) = player.get_single() else {
+    let Ok((player_transform, velocity)) = player.get_single() else {
+        // Snap straight to the next player that spawns
+        follow.position = None;
         return;
     };
 
-    camera_transform.translation = player_transform.translation;
-    // if let Ok(player_transform) = player.get_single() {
-    //     // for (level_transform, level_iid) in &level_query {
-    //     //     let ldtk_project = ldtk_project_assets
-    //     //         .get(ldtk_projects.single())
-    //     //         .expect("Project should be loaded if level has spawned");
-
-    //     //     let level = ldtk_project
-    //     //         .get_raw_level_by_iid(&level_iid.to_string())
-    //     //         .expect("Spawned level should exist in LDtk project");
-
-    //     //     if level_selection.is_match(&LevelIndices::default(), level) {
-    //     //         let top_right = (IVec2::new(level.px_wid, level.px_hei).as_vec2() / 2.).extend(0.);
-    //     //         let bottom_left = -top_right;
-
-    //     //         let min_camera_position =
-    //     //             bottom_left - projection.area.min.extend(0.) + level_transform.translation;
-    //     //         let max_camera_position =
-    //     //             top_right - projection.area.max.extend(0.) + level_transform.translation;
-    //     //         camera_transform.translation = player_transform
-    //     //             .translation
-    //     //             .clamp(min_camera_position, max_camera_position);
-    //     //     }
-    //     // }
-    // }
+    let lookahead = velocity.map_or(Vec2::ZERO, |velocity| {
+        (velocity.value.truncate() * follow.lookahead).clamp_length_max(follow.max_lookahead)
+    });
+    let focus = player_transform.translation.truncate() + lookahead;
+
+    let mut target = match follow.position {
+        Some(position) => {
+            // Only follow the part of the movement that leaves the deadzone
+            let offset = focus - position;
+            let outside = offset - offset.clamp(-follow.deadzone, follow.deadzone);
+            let target = position + outside;
+            let t = 1.0 - (-follow.smoothing * time.delta_seconds()).exp();
+            position.lerp(target, t)
+        }
+        None => focus,
+    };
+
+    let project = ldtk_projects
+        .get_single()
+        .ok()
+        .and_then(|handle| ldtk_project_assets.get(handle));
+    if let Some(project) = project {
+        for (level_transform, level_iid) in &level_query {
+            let Some(level) = project.get_raw_level_by_iid(&level_iid.to_string()) else {
+                continue;
+            };
+            if level_selection.is_match(&LevelIndices::default(), level) {
+                // Levels are anchored at their bottom left corner
+                let bottom_left = level_transform.translation.truncate();
+                let size = IVec2::new(level.px_wid, level.px_hei).as_vec2();
+                target = clamp_to_bounds(
+                    target,
+                    Rect::from_corners(bottom_left, bottom_left + size),
+                    projection.area,
+                );
+            }
+        }
+    }
+
+    follow.position = Some(target);
+
+    // Snap to whole screen pixels so the nearest filtered tiles don't shimmer,
+    // while the unsnapped position keeps the smoothing continuous
+    let pixel = projection.scale;
+    let snapped = (target / pixel).round() * pixel;
+
+    // Only move in the plane, the camera keeps its own depth so everything stays in view
+    camera_transform.translation = snapped.extend(camera_transform.translation.z);
+}
+
+/// Clamps a camera position so the visible `area` stays inside `bounds`.
+/// Along any axis where the bounds are smaller than the view, the camera is centered instead.
+fn clamp_to_bounds(position: Vec2, bounds: Rect, area: Rect) -> Vec2 {
+    let min = bounds.min - area.min;
+    let max = bounds.max - area.max;
+    let center = bounds.center();
+    Vec2::new(
+        if min.x <= max.x {
+            position.x.clamp(min.x, max.x)
+        } else {
+            center.x
+        },
+        if min.y <= max.y {
+            position.y.clamp(min.y, max.y)
+        } else {
+            center.y
+        },
+    )
 }
@@ -5,6 +5,7 @@ use bevy::sprite::MaterialMesh2dBundle;
 use bevy_ecs_ldtk::prelude::*;
 
 use crate::{
+    damage::DamageType,
     enemies::Enemy,
     player::{Player, Projectile},
     schedule::InGame,
@@ -57,12 +58,13 @@ impl From<&EntityInstance> for Collider {
 #[derive(Component, Debug, Clone, Default)]
 pub struct CollisionDamage {
     pub amount: u32,
+    pub damage_type: DamageType,
 }
 
 impl From<&EntityInstance> for CollisionDamage {
     fn from(value: &EntityInstance) -> Self {
         if let Ok(v) = value.get_int_field("collision_damage") {
-            Self { amount: *v as u32 }
+            Self::new(*v as u32)
         } else {
             Default::default()
         }
@@ -71,7 +73,14 @@ impl From<&EntityInstance> for CollisionDamage {
 
 impl CollisionDamage {
     pub fn new(amount: u32) -> Self {
-        Self { amount }
+        Self::with_type(amount, DamageType::Physical)
+    }
+
+    pub fn with_type(amount: u32, damage_type: DamageType) -> Self {
+        Self {
+            amount,
+            damage_type,
+        }
     }
 }
 
@@ -108,27 +117,69 @@ impl Collider {
     }
 }
 
-fn detect_collisions(mut query: Query<(Entity, &Transform, &mut Collider)>) {
+/// Where the segment from `start` to `end` first touches `rect`, as a fraction of its length.
+/// Segments starting inside the rect touch it at 0.0.
+pub fn segment_entry(start: Vec2, end: Vec2, rect: Rect) -> Option<f32> {
+    let delta = end - start;
+    let (mut enter, mut exit) = (0.0f32, 1.0f32);
+    for axis in 0..2 {
+        let (from, step) = (start[axis], delta[axis]);
+        let (min, max) = (rect.min[axis], rect.max[axis]);
+        if step.abs() < f32::EPSILON {
+            // Parallel to this pair of edges, so it has to run between them
+            if from < min || from > max {
+                return None;
+            }
+            continue;
+        }
+        let (near, far) = {
+            let (a, b) = ((min - from) / step, (max - from) / step);
+            (a.min(b), a.max(b))
+        };
+        enter = enter.max(near);
+        exit = exit.min(far);
+        if enter > exit {
+            return None;
+        }
+    }
+    Some(enter)
+}
+
+fn detect_collisions(
+    mut query: Query<(Entity, &Transform, Option<&Parent>, &mut Collider)>,
+    colliding_parents: Query<&Transform, With<Collider>>,
+) {
+    // Colliders attached to another colliding entity, like the player's orbiting weapons, are
+    // positioned relative to it
+    let rects: Vec<(Entity, Rect)> = query
+        .iter()
+        .map(|(entity, transform, parent, collider)| {
+            let parent_transform =
+                parent.and_then(|parent| colliding_parents.get(parent.get()).ok());
+            let transform = parent_transform.map_or(*transform, |parent_transform| {
+                parent_transform.mul_transform(*transform)
+            });
+            (entity, collider.to_rect_at(&transform))
+        })
+        .collect();
+
     let mut collisions: HashMap<Entity, Vec<Entity>> = HashMap::new();
     // Detection
-    for (entity_a, transform_a, collider_a) in query.iter() {
-        let rect_a = collider_a.to_rect_at(transform_a);
-
-        for (entity_b, transform_b, collider_b) in query.iter() {
+    for (entity_a, rect_a) in rects.iter() {
+        for (entity_b, rect_b) in rects.iter() {
             // Don't collide with yourself
             if entity_b == entity_a {
                 continue;
             }
 
-            let rect_b = collider_b.to_rect_at(transform_b);
-            if !rect_a.intersect(rect_b).is_empty() {
-                collisions.entry(entity_a).or_default().push(entity_b);
+            if !rect_a.intersect(*rect_b).is_empty() {
+                collisions.entry(*entity_a).or_default().push(*entity_b);
             }
         }
     }
 
     // Record
-    for (entity, _, mut collider) in query.iter_mut() {
+    for (entity, _, _, mut collider) in query.iter_mut() {
         collider.collisions = collisions.remove(&entity).unwrap_or_default();
     }
 }
@@ -172,3 +223,66 @@ fn setup_collision_gizmos(
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Rect {
+        Rect::new(0., 0., 10., 10.)
+    }
+
+    #[test]
+    fn segment_entering_from_outside() {
+        let entry = segment_entry(Vec2::new(-10., 5.), Vec2::new(10., 5.), square());
+        assert_eq!(entry, Some(0.5));
+    }
+
+    #[test]
+    fn segment_starting_inside() {
+        let entry = segment_entry(Vec2::new(5., 5.), Vec2::new(20., 5.), square());
+        assert_eq!(entry, Some(0.0));
+    }
+
+    #[test]
+    fn segment_stopping_short() {
+        let entry = segment_entry(Vec2::new(-10., 5.), Vec2::new(-1., 5.), square());
+        assert_eq!(entry, None);
+    }
+
+    #[test]
+    fn segment_passing_beside() {
+        let entry = segment_entry(Vec2::new(-10., 15.), Vec2::new(20., 15.), square());
+        assert_eq!(entry, None);
+    }
+
+    #[test]
+    fn diagonal_segment_missing_corner() {
+        let entry = segment_entry(Vec2::new(-10., 5.), Vec2::new(5., 20.), square());
+        assert_eq!(entry, None);
+    }
+
+    #[test]
+    fn parallel_segment_between_edges() {
+        let entry = segment_entry(Vec2::new(5., -10.), Vec2::new(5., 30.), square());
+        assert_eq!(entry, Some(0.25));
+    }
+
+    #[test]
+    fn parallel_segment_outside_edges() {
+        let entry = segment_entry(Vec2::new(11., -10.), Vec2::new(11., 30.), square());
+        assert_eq!(entry, None);
+    }
+
+    #[test]
+    fn zero_length_segment() {
+        assert_eq!(
+            segment_entry(Vec2::splat(5.), Vec2::splat(5.), square()),
+            Some(0.0)
+        );
+        assert_eq!(
+            segment_entry(Vec2::splat(-5.), Vec2::splat(-5.), square()),
+            None
+        );
+    }
+}
@@ -2,7 +2,13 @@ use std::ops::AddAssign;
 
 use bevy::{prelude::*, utils::HashMap};
 
-use crate::{collision::CollisionEvent, enemies::Enemy, player::Player, schedule::InGame};
+use crate::{
+    collision::{Collider, CollisionEvent},
+    enemies::Enemy,
+    levels::Wall,
+    player::{Player, Projectile},
+    schedule::InGame,
+};
 
 pub struct CombatPlugin;
 
@@ -11,7 +17,11 @@ impl Plugin for CombatPlugin {
         app.add_systems(Update, apply_knockback.in_set(InGame::EntityUpdates))
             .add_systems(
                 Update,
-                knockback_collisions::<Player, Enemy>.in_set(InGame::ProcessCombat),
+                (
+                    knockback_collisions::<Player, Enemy>,
+                    knockback_collisions::<Enemy, Projectile>,
+                )
+                    .in_set(InGame::ProcessCombat),
             );
     }
 }
@@ -25,16 +35,42 @@ pub struct KnockBack {
     pub duration: Timer,
 }
 
+/// How far an entity pushes whatever it collides with.
+/// Entities without this component push with [`KNOCK_BACK_DISTANCE`].
+#[derive(Component, Debug, Clone, Copy)]
+pub struct KnockBackForce(pub f32);
+
+/// Reduces the distance an entity is knocked back, from 0.0 (no resistance) to 1.0 (immune).
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct KnockBackResistance(pub f32);
+
 fn apply_knockback(
-    mut query: Query<(Entity, &mut KnockBack, &mut Transform)>,
+    mut query: Query<(Entity, &mut KnockBack, &mut Transform, Option<&Collider>), Without<Wall>>,
+    walls: Query<(&Transform, &Collider), With<Wall>>,
     time: Res<Time>,
     mut commands: Commands,
 ) {
-    for (entity, mut knockback, mut transform) in query.iter_mut() {
+    for (entity, mut knockback, mut transform, collider) in query.iter_mut() {
         knockback.duration.tick(time.delta());
-        transform.translation += knockback.displacement
+        let step = knockback.displacement
             * (time.delta_seconds() / knockback.duration.duration().as_secs_f32());
-        if knockback.duration.finished() {
+
+        // Stop the knockback short rather than pushing the entity into a wall
+        let mut moved = transform.with_translation(transform.translation + step);
+        let blocked = collider.is_some_and(|collider| {
+            let rect = collider.to_rect_at(&moved);
+            walls.iter().any(|(wall_transform, wall_collider)| {
+                !rect
+                    .intersect(wall_collider.to_rect_at(wall_transform))
+                    .is_empty()
+            })
+        });
+        if blocked {
+            moved = *transform;
+        }
+        transform.translation = moved.translation;
+
+        if blocked || knockback.duration.finished() {
             commands.entity(entity).remove::<KnockBack>();
         }
     }
@@ -43,29 +79,39 @@ fn apply_knockback(
 fn knockback_collisions<T: Component, C: Component>(
     mut commands: Commands,
     mut events: EventReader<CollisionEvent>,
-    receivers: Query<&Transform, (With<T>, Without<KnockBack>)>,
-    pushers: Query<&Transform, With<C>>,
+    receivers: Query<(&Transform, Option<&KnockBackResistance>), (With<T>, Without<KnockBack>)>,
+    pushers: Query<(&Transform, Option<&KnockBackForce>), With<C>>,
 ) {
-    let mut knockbacks: HashMap<Entity, Vec3> = HashMap::new();
+    // The summed push direction and the strongest push for each receiver
+    let mut knockbacks: HashMap<Entity, (Vec3, f32)> = HashMap::new();
     for event in events.read() {
-        let Ok(receiver_transform) = receivers.get(event.entity) else {
+        let Ok((receiver_transform, resistance)) = receivers.get(event.entity) else {
             continue;
         };
 
-        let Ok(pusher_transform) = pushers.get(event.collided_with) else {
+        let Ok((pusher_transform, force)) = pushers.get(event.collided_with) else {
             continue;
         };
 
+        let force = force.map_or(KNOCK_BACK_DISTANCE, |force| force.0);
+        let resistance = resistance.map_or(0.0, |resistance| resistance.0.clamp(0.0, 1.0));
+        let distance = force * (1.0 - resistance);
         let push_direction = receiver_transform.translation - pusher_transform.translation;
         knockbacks
             .entry(event.entity)
-            .and_modify(|direction| direction.add_assign(push_direction))
-            .or_insert(push_direction);
+            .and_modify(|(direction, strongest)| {
+                direction.add_assign(push_direction);
+                *strongest = strongest.max(distance);
+            })
+            .or_insert((push_direction, distance));
     }
 
-    for (entity, direction) in knockbacks.into_iter() {
+    for (entity, (direction, distance)) in knockbacks.into_iter() {
+        if distance <= 0.0 {
+            continue;
+        }
         commands.entity(entity).insert(KnockBack {
-            displacement: direction.normalize_or_zero() * KNOCK_BACK_DISTANCE,
+            displacement: direction.normalize_or_zero() * distance,
             duration: Timer::from_seconds(KNOCK_BACK_DURATION, TimerMode::Once),
         });
     }
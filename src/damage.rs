@@ -0,0 +1,446 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    collision::{CollisionDamage, CollisionEvent},
+    enemies::Enemy,
+    health::{DamageCooldown, DamageEvent, Health, Shield},
+    player::{Player, Projectile, WeaponType},
+    rng::RunRng,
+    schedule::InGame,
+};
+
+/// Damage multiplier for hits of a type an entity resists
+const RESISTANCE_MULTIPLIER: f32 = 0.25;
+/// Damage multiplier for hits of a type an entity is weak to
+const WEAKNESS_MULTIPLIER: f32 = 2.0;
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageRequest>()
+            .add_event::<DeathEvent>()
+            .init_resource::<PendingDamage>()
+            .configure_sets(
+                Update,
+                (
+                    DamagePipeline::Request,
+                    DamagePipeline::Modify,
+                    DamagePipeline::Apply,
+                )
+                    .chain()
+                    .in_set(InGame::ProcessCombat),
+            )
+            .add_systems(
+                Update,
+                (
+                    request_collision_damage::<Player, Enemy>,
+                    request_collision_damage::<Enemy, Projectile>,
+                    request_collision_damage::<Projectile, Enemy>,
+                )
+                    .in_set(DamagePipeline::Request),
+            )
+            .add_systems(
+                Update,
+                collect_damage_requests
+                    .after(DamagePipeline::Request)
+                    .before(DamagePipeline::Modify)
+                    .in_set(InGame::ProcessCombat),
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_invulnerability,
+                    roll_damage,
+                    apply_resistances,
+                    apply_vulnerability,
+                    apply_armor,
+                )
+                    .chain()
+                    .in_set(DamagePipeline::Modify),
+            )
+            .add_systems(Update, apply_damage.in_set(DamagePipeline::Apply));
+    }
+}
+
+/// The stages every hit goes through, in order, during [`InGame::ProcessCombat`].
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
+pub enum DamagePipeline {
+    /// Systems that send [`DamageRequest`]s
+    Request,
+    /// Systems that adjust or cancel the hits in [`PendingDamage`]
+    Modify,
+    /// Hits are subtracted from [`Health`] and deaths are announced
+    Apply,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    #[default]
+    Physical,
+    Fire,
+    Ice,
+    Lightning,
+    Poison,
+    Holy,
+}
+
+impl DamageType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "physical" => Some(DamageType::Physical),
+            "fire" => Some(DamageType::Fire),
+            "ice" => Some(DamageType::Ice),
+            "lightning" => Some(DamageType::Lightning),
+            "poison" => Some(DamageType::Poison),
+            "holy" => Some(DamageType::Holy),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            DamageType::Physical => Color::ORANGE_RED,
+            DamageType::Fire => Color::ORANGE,
+            DamageType::Ice => Color::CYAN,
+            DamageType::Lightning => Color::YELLOW,
+            DamageType::Poison => Color::LIME_GREEN,
+            DamageType::Holy => Color::ANTIQUE_WHITE,
+        }
+    }
+}
+
+/// How a hit was affected by the receiver's [`Resistances`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Effectiveness {
+    #[default]
+    Normal,
+    Weak,
+    Resisted,
+}
+
+/// Per damage type multipliers for incoming hits.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Resistances(HashMap<DamageType, f32>);
+
+impl Resistances {
+    /// Builds resistances from comma separated lists of damage type names,
+    /// as they are written in the LDtk enemy fields.
+    pub fn from_names(resistances: &str, weaknesses: &str) -> Self {
+        let mut multipliers = HashMap::new();
+        for (names, multiplier) in [
+            (resistances, RESISTANCE_MULTIPLIER),
+            (weaknesses, WEAKNESS_MULTIPLIER),
+        ] {
+            for name in names.split(',').filter(|name| !name.trim().is_empty()) {
+                match DamageType::from_name(name) {
+                    Some(damage_type) => {
+                        multipliers.insert(damage_type, multiplier);
+                    }
+                    None => error!("Unknown damage type {name:?}"),
+                }
+            }
+        }
+        Self(multipliers)
+    }
+
+    pub fn multiplier(&self, damage_type: DamageType) -> f32 {
+        self.0.get(&damage_type).copied().unwrap_or(1.0)
+    }
+}
+
+/// The chance of landing a critical hit and the damage multiplier when one lands.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct CritStats {
+    pub chance: f32,
+    pub multiplier: f32,
+}
+
+impl Default for CritStats {
+    fn default() -> Self {
+        Self {
+            chance: 0.0,
+            multiplier: 1.0,
+        }
+    }
+}
+
+impl CritStats {
+    pub fn new(chance: f32, multiplier: f32) -> Self {
+        Self { chance, multiplier }
+    }
+
+    /// Chances add up while multipliers compound
+    pub fn combine(&self, other: &CritStats) -> Self {
+        Self {
+            chance: self.chance + other.chance,
+            multiplier: self.multiplier * other.multiplier,
+        }
+    }
+}
+
+/// How the damage dealt by an entity is rolled on each hit.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct DamageRoll {
+    pub crit: CritStats,
+    /// Damage is scaled by a random amount in `1.0 - variance ..= 1.0 + variance`
+    pub variance: f32,
+}
+
+/// Who is responsible for the damage an entity deals, so that kills can be credited.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct DamageSource {
+    pub attacker: Option<Entity>,
+    pub weapon: Option<WeaponType>,
+}
+
+/// Asks for damage to be dealt to `target`, subject to its modifiers.
+#[derive(Debug, Clone, Event)]
+pub struct DamageRequest {
+    pub target: Entity,
+    pub amount: u32,
+    pub damage_type: DamageType,
+    pub source: DamageSource,
+    pub roll: DamageRoll,
+}
+
+/// A hit that is making its way through the modifier stage.
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub target: Entity,
+    pub amount: f32,
+    pub damage_type: DamageType,
+    pub effectiveness: Effectiveness,
+    pub source: DamageSource,
+    pub roll: DamageRoll,
+    pub critical: bool,
+    pub cancelled: bool,
+}
+
+impl From<&DamageRequest> for Hit {
+    fn from(request: &DamageRequest) -> Self {
+        Self {
+            target: request.target,
+            amount: request.amount as f32,
+            damage_type: request.damage_type,
+            effectiveness: Effectiveness::Normal,
+            source: request.source,
+            roll: request.roll,
+            critical: false,
+            cancelled: false,
+        }
+    }
+}
+
+/// The hits requested this frame that haven't been applied yet.
+#[derive(Resource, Debug, Default)]
+pub struct PendingDamage(pub Vec<Hit>);
+
+/// Sent when an entity's health reaches zero. The entity is despawned at the end of the frame.
+#[derive(Debug, Clone, Event)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub killer: Option<Entity>,
+    pub weapon: Option<WeaponType>,
+    pub position: Vec3,
+}
+
+/// Flat reduction to every incoming hit. Hits always deal at least 1 damage.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Armor(pub u32);
+
+/// Multiplies all incoming damage.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Vulnerability(pub f32);
+
+fn request_collision_damage<T: Component, E: Component>(
+    mut events: EventReader<CollisionEvent>,
+    mut requests: EventWriter<DamageRequest>,
+    receiver: Query<(), With<T>>,
+    damager: Query<(&CollisionDamage, Option<&DamageSource>, Option<&DamageRoll>), With<E>>,
+) {
+    for collision in events.read() {
+        if !receiver.contains(collision.entity) {
+            continue;
+        }
+
+        let Ok((damage, source, roll)) = damager.get(collision.collided_with) else {
+            continue;
+        };
+
+        requests.send(DamageRequest {
+            target: collision.entity,
+            amount: damage.amount,
+            damage_type: damage.damage_type,
+            source: source.copied().unwrap_or(DamageSource {
+                attacker: Some(collision.collided_with),
+                weapon: None,
+            }),
+            roll: roll.copied().unwrap_or_default(),
+        });
+    }
+}
+
+fn collect_damage_requests(
+    mut requests: EventReader<DamageRequest>,
+    mut pending: ResMut<PendingDamage>,
+) {
+    pending.0.extend(requests.read().map(Hit::from));
+}
+
+fn apply_invulnerability(
+    mut pending: ResMut<PendingDamage>,
+    invulnerable: Query<(), With<DamageCooldown>>,
+) {
+    for hit in pending.0.iter_mut() {
+        if invulnerable.contains(hit.target) {
+            hit.cancelled = true;
+        }
+    }
+}
+
+fn roll_damage(mut pending: ResMut<PendingDamage>, mut rng: ResMut<RunRng>) {
+    for hit in pending.0.iter_mut().filter(|hit| !hit.cancelled) {
+        hit.amount *= rng.variance(hit.roll.variance);
+        if rng.chance(hit.roll.crit.chance) {
+            hit.amount *= hit.roll.crit.multiplier;
+            hit.critical = true;
+        }
+    }
+}
+
+fn apply_resistances(mut pending: ResMut<PendingDamage>, query: Query<&Resistances>) {
+    for hit in pending.0.iter_mut() {
+        let Ok(resistances) = query.get(hit.target) else {
+            continue;
+        };
+        let multiplier = resistances.multiplier(hit.damage_type);
+        hit.amount *= multiplier;
+        hit.effectiveness = if multiplier > 1.0 {
+            Effectiveness::Weak
+        } else if multiplier < 1.0 {
+            Effectiveness::Resisted
+        } else {
+            Effectiveness::Normal
+        };
+    }
+}
+
+fn apply_vulnerability(mut pending: ResMut<PendingDamage>, query: Query<&Vulnerability>) {
+    for hit in pending.0.iter_mut() {
+        if let Ok(vulnerability) = query.get(hit.target) {
+            hit.amount *= vulnerability.0;
+        }
+    }
+}
+
+fn apply_armor(mut pending: ResMut<PendingDamage>, query: Query<&Armor>) {
+    for hit in pending.0.iter_mut().filter(|hit| !hit.cancelled) {
+        if let Ok(armor) = query.get(hit.target) {
+            hit.amount = (hit.amount - armor.0 as f32).max(1.0);
+        }
+    }
+}
+
+fn apply_damage(
+    mut commands: Commands,
+    mut pending: ResMut<PendingDamage>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut death_events: EventWriter<DeathEvent>,
+    mut query: Query<(&mut Health, Option<&mut Shield>, &Transform)>,
+) {
+    for hit in pending.0.drain(..) {
+        if hit.cancelled {
+            continue;
+        }
+
+        let Ok((mut health, shield, transform)) = query.get_mut(hit.target) else {
+            continue;
+        };
+
+        // Already died from an earlier hit this frame
+        if health.amount == 0 {
+            continue;
+        }
+
+        let mut amount = hit.amount.round() as u32;
+        let absorbed = shield.map_or(0, |mut shield| shield.absorb(amount));
+        amount -= absorbed;
+        health.amount = health.amount.saturating_sub(amount);
+
+        damage_events.send(DamageEvent {
+            amount,
+            position: transform.translation,
+            receiver: hit.target,
+            damage_type: hit.damage_type,
+            effectiveness: hit.effectiveness,
+            critical: hit.critical,
+            absorbed,
+            source: hit.source,
+        });
+
+        if health.amount == 0 {
+            death_events.send(DeathEvent {
+                entity: hit.target,
+                killer: hit.source.attacker,
+                weapon: hit.source.weapon,
+                position: transform.translation,
+            });
+        } else if let Some(duration) = health.cooldown.as_ref() {
+            commands
+                .entity(hit.target)
+                .insert(DamageCooldown::new(*duration));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// Rolls the same ten hits with a fresh [`RunRng`] seeded with `seed`
+    fn rolled_hits(seed: u64) -> Vec<(f32, bool)> {
+        let mut world = World::new();
+        world.insert_resource(RunRng::from_seed(seed));
+        let request = DamageRequest {
+            target: Entity::PLACEHOLDER,
+            amount: 10,
+            damage_type: DamageType::Physical,
+            source: DamageSource::default(),
+            roll: DamageRoll {
+                crit: CritStats::new(0.5, 2.0),
+                variance: 0.25,
+            },
+        };
+        world.insert_resource(PendingDamage(vec![Hit::from(&request); 10]));
+        world.run_system_once(roll_damage);
+        world
+            .resource::<PendingDamage>()
+            .0
+            .iter()
+            .map(|hit| (hit.amount, hit.critical))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_damage() {
+        assert_eq!(rolled_hits(99), rolled_hits(99));
+    }
+
+    #[test]
+    fn rolls_stay_within_variance_and_crit() {
+        for (amount, critical) in rolled_hits(5) {
+            let base = if critical { 20.0 } else { 10.0 };
+            assert!(amount >= base * 0.75 && amount <= base * 1.25);
+        }
+    }
+
+    #[test]
+    fn crit_stats_combine() {
+        let combined = CritStats::new(0.05, 1.5).combine(&CritStats::new(0.1, 2.0));
+        assert!((combined.chance - 0.15).abs() < f32::EPSILON);
+        assert_eq!(combined.multiplier, 3.0);
+    }
+}
@@ -1,61 +1,211 @@
-use bevy::ecs::query::WorldQuery;
+use std::collections::HashMap;
+
+use bevy::ecs::system::Command;
 use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::EntityDefinition;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_ecs_ldtk::utils::{grid_coords_to_translation, translation_to_grid_coords};
 use rand::Rng as _;
-// use bevy_ecs_ldtk::utils::{grid_coords_to_translation, translation_to_grid_coords};
-// use rand::Rng;
 
-// use crate::asset_loader::SpriteAssets;
+use crate::asset_loader::SpriteAssets;
 use crate::collision::{Collider, CollisionDamage};
-use crate::health::Health;
+use crate::combat::KnockBackResistance;
+use crate::damage::{Armor, Resistances};
+use crate::health::{Health, Shield};
 use crate::levels::{ActiveSpawnList, SpawnLocations};
-// use crate::levels::SpawnLocations;
 use crate::movement::{Facing, MovementBundle, Velocity};
 use crate::player::Player;
 use crate::schedule::InGame;
+use crate::status::Frozen;
 
 const SPAWN_INTERVAL: f32 = 0.5;
-const ENEMY_ENTITIES: [&str; 5] = ["ghost", "cyclops", "crab", "bat", "spider"];
+/// LDtk entity definitions carrying this tag are loaded into the [`EnemyRegistry`]
+const ENEMY_TAG: &str = "enemy";
+/// Number of columns in the tiny-dungeon tilemap, used to turn tile rects into atlas indices
+const TILEMAP_COLUMNS: i32 = 12;
 
 #[derive(Component, Debug, Default)]
 pub struct Enemy;
 
-#[derive(Component, Debug, Default)]
-pub struct EnemyPrototype(String);
+/// The [`EnemyRegistry`] id an enemy was spawned from.
+#[derive(Component, Debug, Clone, Default)]
+pub struct EnemyKind(pub String);
+
+/// The typed data needed to spawn one kind of enemy, read from its LDtk entity definition.
+#[derive(Debug, Clone)]
+pub struct EnemyPrototype {
+    pub id: String,
+    pub sprite_index: usize,
+    pub collider: Collider,
+    pub damage: CollisionDamage,
+    pub health: Health,
+    pub speed: f32,
+    pub knockback_resistance: f32,
+    pub resistances: Resistances,
+    pub armor: u32,
+    /// Shield capacity, enemies without one get no [`Shield`] component
+    pub shield: u32,
+}
 
-impl From<&EntityInstance> for EnemyPrototype {
-    fn from(value: &EntityInstance) -> Self {
-        Self(value.identifier.clone())
+impl EnemyPrototype {
+    fn from_definition(definition: &EntityDefinition) -> Self {
+        let sprite_index = definition
+            .tile_rect
+            .as_ref()
+            .map(|rect| ((rect.y / rect.h) * TILEMAP_COLUMNS + rect.x / rect.w) as usize)
+            .unwrap_or_default();
+        let float_field = |identifier: &str| default_float_field(definition, identifier);
+        let string_field =
+            |identifier: &str| default_string_field(definition, identifier).unwrap_or_default();
+        let collider = match (
+            float_field("collider_width"),
+            float_field("collider_height"),
+        ) {
+            (Some(width), Some(height)) => Collider::new(Vec2::new(width, height)),
+            _ => Default::default(),
+        };
+        Self {
+            id: definition.identifier.clone(),
+            sprite_index,
+            collider,
+            damage: CollisionDamage::new(float_field("collision_damage").unwrap_or_default() as u32),
+            health: Health::new(float_field("health").unwrap_or_default() as u32),
+            speed: float_field("speed").unwrap_or_default(),
+            knockback_resistance: float_field("knockback_resistance").unwrap_or_default(),
+            resistances: Resistances::from_names(
+                string_field("resistances"),
+                string_field("weaknesses"),
+            ),
+            armor: float_field("armor").unwrap_or_default() as u32,
+            shield: float_field("shield").unwrap_or_default() as u32,
+        }
+    }
+
+    pub fn bundle(&self, texture_atlas: Handle<TextureAtlas>, position: Vec2) -> EnemyBundle {
+        EnemyBundle {
+            enemy: Enemy,
+            kind: EnemyKind(self.id.clone()),
+            sprite: SpriteSheetBundle {
+                sprite: TextureAtlasSprite::new(self.sprite_index),
+                texture_atlas,
+                transform: Transform::from_translation(position.extend(100.)),
+                ..Default::default()
+            },
+            collider: self.collider.clone(),
+            damage: self.damage.clone(),
+            health: self.health.clone(),
+            knockback_resistance: KnockBackResistance(self.knockback_resistance),
+            resistances: self.resistances.clone(),
+            armor: Armor(self.armor),
+            movement: MovementBundle {
+                velocity: Velocity::from_direction_speed(Vec3::X, self.speed),
+                ..Default::default()
+            },
+        }
     }
 }
 
-#[derive(Bundle, LdtkEntity, Default)]
-pub struct EnemyPrototypeBundle {
-    #[from_entity_instance]
-    enemy: EnemyPrototype,
-    #[sprite_sheet_bundle]
-    sprite: SpriteSheetBundle,
-    #[from_entity_instance]
-    collider: Collider,
-    #[from_entity_instance]
-    damage: CollisionDamage,
-    #[from_entity_instance]
-    health: Health,
-    #[from_entity_instance]
-    movement: MovementBundle,
+/// Reads the default value of a numeric field from an LDtk entity definition.
+fn default_float_field(definition: &EntityDefinition, identifier: &str) -> Option<f32> {
+    definition
+        .field_defs
+        .iter()
+        .find(|field| field.identifier == identifier)?
+        .default_override
+        .as_ref()?
+        .get("params")?
+        .get(0)?
+        .as_f64()
+        .map(|value| value as f32)
+}
+
+/// Reads the default value of a string field from an LDtk entity definition.
+fn default_string_field<'a>(definition: &'a EntityDefinition, identifier: &str) -> Option<&'a str> {
+    definition
+        .field_defs
+        .iter()
+        .find(|field| field.identifier == identifier)?
+        .default_override
+        .as_ref()?
+        .get("params")?
+        .get(0)?
+        .as_str()
+}
+
+/// All the kinds of enemies that can be spawned, keyed by their LDtk identifier.
+#[derive(Resource, Debug, Default)]
+pub struct EnemyRegistry(HashMap<String, EnemyPrototype>);
+
+impl EnemyRegistry {
+    pub fn from_definitions<'a>(
+        definitions: impl IntoIterator<Item = &'a EntityDefinition>,
+    ) -> Self {
+        Self(
+            definitions
+                .into_iter()
+                .filter(|definition| definition.tags.iter().any(|tag| tag == ENEMY_TAG))
+                .map(|definition| {
+                    (
+                        definition.identifier.clone(),
+                        EnemyPrototype::from_definition(definition),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, id: &str) -> Option<&EnemyPrototype> {
+        self.0.get(id)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.0.contains_key(id)
+    }
 }
 
 #[derive(Bundle, Default)]
 pub struct EnemyBundle {
     enemy: Enemy,
+    kind: EnemyKind,
     sprite: SpriteSheetBundle,
     collider: Collider,
     damage: CollisionDamage,
     health: Health,
+    knockback_resistance: KnockBackResistance,
+    resistances: Resistances,
+    armor: Armor,
     movement: MovementBundle,
 }
 
+/// Spawns the enemy registered under `id` once commands are applied.
+pub struct SpawnEnemy {
+    pub id: String,
+    pub position: Vec2,
+}
+
+impl Command for SpawnEnemy {
+    fn apply(self, world: &mut World) {
+        let Some(prototype) = world.resource::<EnemyRegistry>().get(&self.id) else {
+            error!("Tried to spawn unknown enemy {:?}", self.id);
+            return;
+        };
+        let texture_atlas = world.resource::<SpriteAssets>().tiles.clone();
+        let bundle = prototype.bundle(texture_atlas, self.position);
+        let shield = prototype.shield;
+        let mut enemy = world.spawn(bundle);
+        if shield > 0 {
+            enemy.insert(Shield::new(shield));
+        }
+    }
+}
+
+pub fn spawn_enemy_by_id(commands: &mut Commands, id: &str, position: Vec2) {
+    commands.add(SpawnEnemy {
+        id: id.to_owned(),
+        position,
+    });
+}
+
 pub struct EnemiesPlugin;
 
 #[derive(Resource, Debug)]
@@ -69,11 +219,9 @@ impl Default for SpawnTimer {
 
 impl Plugin for EnemiesPlugin {
     fn build(&self, app: &mut App) {
-        let app = app.insert_resource(SpawnTimer::default());
-        for enemy in ENEMY_ENTITIES {
-            app.register_ldtk_entity::<EnemyPrototypeBundle>(enemy);
-        }
-        app.add_systems(Update, cleanup_enemy_prototypes)
+        app.insert_resource(SpawnTimer::default())
+            .init_resource::<EnemyRegistry>()
+            .add_systems(Update, load_enemy_registry)
             .add_systems(
                 Update,
                 (chase_player, spawn_enemy).in_set(InGame::EntityUpdates),
@@ -81,30 +229,29 @@ impl Plugin for EnemiesPlugin {
     }
 }
 
-fn cleanup_enemy_prototypes(query: Query<Entity, Added<EnemyPrototype>>, mut commands: Commands) {
-    for entity in query.iter() {
-        commands.entity(entity).remove::<SpatialBundle>();
+fn load_enemy_registry(
+    mut registry: ResMut<EnemyRegistry>,
+    mut asset_events: EventReader<AssetEvent<LdtkProject>>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        let Some(ldtk_project) = ldtk_project_assets.get(*id) else {
+            continue;
+        };
+        *registry = EnemyRegistry::from_definitions(&ldtk_project.json_data().defs.entities);
+        info!("Loaded {} enemy prototypes", registry.0.len());
     }
 }
 
-#[derive(WorldQuery)]
-struct EnemyPrototypeQuery {
-    prototype: &'static EnemyPrototype,
-    collider: &'static Collider,
-    collision_damage: &'static CollisionDamage,
-    health: &'static Health,
-    sprite: &'static TextureAtlasSprite,
-    atlas: &'static Handle<TextureAtlas>,
-    velocity: &'static Velocity,
-}
-
 #[allow(clippy::too_many_arguments)]
 fn spawn_enemy(
     mut commands: Commands,
     mut timer: ResMut<SpawnTimer>,
     mut spawns: ResMut<ActiveSpawnList>,
     time: Res<Time>,
-    prototypes: Query<EnemyPrototypeQuery>,
     level_selection: Res<LevelSelection>,
     ldtk_projects: Query<&Handle<LdtkProject>>,
     ldtk_project_assets: Res<Assets<LdtkProject>>,
@@ -126,27 +273,7 @@ fn spawn_enemy(
             info!("Couldn't spawn an enemy after 5 attempts");
             return;
         };
-        let transform = Transform::from_translation(spot.extend(100.));
-        for prototype in prototypes.iter() {
-            if prototype.prototype.0 == spawn_id {
-                commands.spawn(EnemyBundle {
-                    enemy: Enemy,
-                    sprite: SpriteSheetBundle {
-                        sprite: prototype.sprite.clone(),
-                        texture_atlas: prototype.atlas.clone(),
-                        transform,
-                        ..Default::default()
-                    },
-                    collider: prototype.collider.clone(),
-                    damage: prototype.collision_damage.clone(),
-                    health: prototype.health.clone(),
-                    movement: MovementBundle {
-                        velocity: prototype.velocity.clone(),
-                        ..Default::default()
-                    },
-                });
-            }
-        }
+        spawn_enemy_by_id(&mut commands, spawn_id, spot);
     }
 }
 
@@ -209,7 +336,7 @@ fn pick_spawn_location(
 }
 
 fn chase_player(
-    mut enemies: Query<(&mut Velocity, &Transform, &mut Facing), With<Enemy>>,
+    mut enemies: Query<(&mut Velocity, &Transform, &mut Facing), (With<Enemy>, Without<Frozen>)>,
     player: Query<&Transform, With<Player>>,
 ) {
     let Ok(player_transform) = player.get_single() else {
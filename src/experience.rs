@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+
+use crate::{
+    damage::{DamagePipeline, DeathEvent},
+    enemies::Enemy,
+    health::Health,
+    player::Player,
+    schedule::InGame,
+};
+
+/// Experience needed to go from level 1 to level 2
+const FIRST_LEVEL_EXPERIENCE: u32 = 20;
+/// Each level needs this much more experience than the one before
+const LEVEL_EXPERIENCE_GROWTH: f32 = 1.25;
+
+pub struct ExperiencePlugin;
+
+impl Plugin for ExperiencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LevelUpEvent>().add_systems(
+            Update,
+            gain_experience
+                .after(DamagePipeline::Apply)
+                .in_set(InGame::ProcessCombat),
+        );
+    }
+}
+
+/// The player's level and progress towards the next one.
+#[derive(Component, Debug, Clone)]
+pub struct Experience {
+    pub level: u32,
+    pub amount: u32,
+    pub next_level: u32,
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self {
+            level: 1,
+            amount: 0,
+            next_level: FIRST_LEVEL_EXPERIENCE,
+        }
+    }
+}
+
+impl Experience {
+    /// Adds experience, returning how many levels were gained
+    pub fn add(&mut self, amount: u32) -> u32 {
+        self.amount += amount;
+        let mut levels = 0;
+        while self.amount >= self.next_level {
+            self.amount -= self.next_level;
+            self.level += 1;
+            self.next_level = (self.next_level as f32 * LEVEL_EXPERIENCE_GROWTH).round() as u32;
+            levels += 1;
+        }
+        levels
+    }
+
+    /// Progress towards the next level, from 0.0 to 1.0
+    pub fn percent(&self) -> f32 {
+        self.amount as f32 / self.next_level as f32
+    }
+}
+
+/// Sent every time the player reaches a new level.
+#[derive(Debug, Clone, Event)]
+pub struct LevelUpEvent {
+    pub level: u32,
+}
+
+/// Enemies are worth their maximum health in experience.
+fn gain_experience(
+    mut deaths: EventReader<DeathEvent>,
+    mut level_ups: EventWriter<LevelUpEvent>,
+    mut players: Query<&mut Experience, With<Player>>,
+    enemies: Query<&Health, With<Enemy>>,
+) {
+    let Ok(mut experience) = players.get_single_mut() else {
+        return;
+    };
+
+    for death in deaths.read() {
+        let Ok(health) = enemies.get(death.entity) else {
+            continue;
+        };
+        for _ in 0..experience.add(health.total) {
+            level_ups.send(LevelUpEvent {
+                level: experience.level,
+            });
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+
+use crate::{
+    health::{DamageCooldown, DamageEvent},
+    schedule::InGame,
+    status::{tint_affected_sprites, StatusEffects},
+};
+
+/// Sprite colors multiply the texture, so the flash is overdriven to saturate every pixel to white
+const FLASH_COLOR: Color = Color::rgb(10., 10., 10.);
+/// Alpha of a blinking sprite during the hidden half of each blink
+const BLINK_ALPHA: f32 = 0.25;
+
+pub struct FlashPlugin;
+
+impl Plugin for FlashPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlashSettings>().add_systems(
+            Update,
+            (flash_on_hit, update_sprite_feedback)
+                .chain()
+                .after(tint_affected_sprites)
+                .in_set(InGame::EntityUpdates),
+        );
+    }
+}
+
+/// Timings for hit flashes and invulnerability blinking.
+#[derive(Resource, Debug, Clone)]
+pub struct FlashSettings {
+    /// Accessibility option, when false sprites never flash or blink
+    pub enabled: bool,
+    pub flash_duration: f32,
+    /// Length of one full visible and hidden cycle while blinking
+    pub blink_interval: f32,
+}
+
+impl Default for FlashSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            flash_duration: 0.08,
+            blink_interval: 0.15,
+        }
+    }
+}
+
+/// Tracks how long ago a sprite was last struck. Kept after the flash ends so its color
+/// can be restored.
+#[derive(Component, Debug)]
+pub struct HitFlash(Timer);
+
+/// Makes the sprite blink while it has a [`DamageCooldown`].
+#[derive(Component, Debug, Default)]
+pub struct BlinkWhileInvulnerable;
+
+fn flash_on_hit(
+    mut commands: Commands,
+    mut events: EventReader<DamageEvent>,
+    mut flashes: Query<&mut HitFlash>,
+    sprites: Query<(), With<TextureAtlasSprite>>,
+    settings: Res<FlashSettings>,
+) {
+    if !settings.enabled {
+        events.clear();
+        return;
+    }
+
+    for event in events.read() {
+        if event.amount == 0 && event.absorbed == 0 {
+            continue;
+        }
+
+        if let Ok(mut flash) = flashes.get_mut(event.receiver) {
+            flash.0 = Timer::from_seconds(settings.flash_duration, TimerMode::Once);
+        } else if sprites.contains(event.receiver) {
+            commands
+                .entity(event.receiver)
+                .insert(HitFlash(Timer::from_seconds(
+                    settings.flash_duration,
+                    TimerMode::Once,
+                )));
+        }
+    }
+}
+
+/// Sets the sprite color from the flash and blink state, on top of any status tint.
+fn update_sprite_feedback(
+    mut query: Query<
+        (
+            &mut TextureAtlasSprite,
+            Option<&mut HitFlash>,
+            Option<&DamageCooldown>,
+            Option<&StatusEffects>,
+            Has<BlinkWhileInvulnerable>,
+        ),
+        Or<(With<HitFlash>, With<BlinkWhileInvulnerable>)>,
+    >,
+    settings: Res<FlashSettings>,
+    time: Res<Time>,
+) {
+    // Blinks are driven by the game clock so every blinking sprite stays in phase
+    let blink_hidden = settings.blink_interval > 0.
+        && time.elapsed_seconds() % settings.blink_interval > settings.blink_interval / 2.;
+
+    for (mut sprite, flash, cooldown, effects, blinks) in query.iter_mut() {
+        let mut color = effects
+            .and_then(StatusEffects::tint)
+            .unwrap_or(Color::WHITE);
+
+        if settings.enabled {
+            if let Some(mut flash) = flash {
+                flash.0.tick(time.delta());
+                if !flash.0.finished() {
+                    color = FLASH_COLOR;
+                }
+            }
+            if blinks && cooldown.is_some() && blink_hidden {
+                color.set_a(BLINK_ALPHA);
+            }
+        }
+
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
@@ -1,38 +1,55 @@
-use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle, utils::HashMap};
 use bevy_ecs_ldtk::prelude::*;
+use rand::random;
 
 use crate::{
     asset_loader::Fonts,
-    collision::{CollisionDamage, CollisionEvent},
-    enemies::Enemy,
-    player::{Player, Projectile},
+    damage::{DamagePipeline, DamageSource, DamageType, Effectiveness},
     schedule::InGame,
 };
 
+const SHIELD_RECHARGE_DELAY: f32 = 3.0;
+const SHIELD_RECHARGE_RATE: f32 = 2.0;
+const SHIELD_COLOR: Color = Color::rgb(0.3, 0.6, 1.0);
+
+const DAMAGE_LABEL_DURATION: f32 = 0.5;
+/// Hits on a receiver whose number is younger than this are added to that number
+const DAMAGE_LABEL_MERGE_WINDOW: f32 = 0.3;
+const DAMAGE_LABEL_RISE_SPEED: f32 = 30.;
+/// Maximum horizontal drift speed, picked at random for each number
+const DAMAGE_LABEL_JITTER: f32 = 15.;
+/// Extra font size per merged hit, as a fraction of the base size
+const DAMAGE_LABEL_GROWTH: f32 = 0.1;
+const DAMAGE_LABEL_MAX_GROWTH: f32 = 2.0;
+const MAX_DAMAGE_LABELS: usize = 32;
+
 pub struct HealthPlugin;
 
 impl Plugin for HealthPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<DamageEvent>()
+            .add_event::<HealEvent>()
+            .init_resource::<DamageNumbers>()
             .add_systems(
                 Update,
-                (
-                    take_damage::<Player, Enemy>,
-                    take_damage::<Enemy, Projectile>,
-                    take_damage::<Projectile, Enemy>,
-                    despawn_dead_entities,
-                )
-                    .chain()
+                despawn_dead_entities
+                    .after(DamagePipeline::Apply)
                     .in_set(InGame::ProcessCombat),
             )
+            .add_systems(
+                Update,
+                (regenerate, apply_healing, recharge_shields)
+                    .chain()
+                    .in_set(InGame::EntityUpdates),
+            )
             .add_systems(
                 Update,
                 (
                     tick_damage_cooldown,
                     create_health_bars,
                     update_health_bars,
-                    display_damage,
-                    tick_damage_display,
+                    update_shield_bars,
+                    (display_damage, limit_damage_displays, tick_damage_display).chain(),
                 )
                     .in_set(InGame::EntityUpdates),
             );
@@ -46,12 +63,62 @@ pub struct Health {
     pub cooldown: Option<f32>,
 }
 
+/// Restores health over time, in whole points.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Regeneration {
+    pub per_second: f32,
+    progress: f32,
+}
+
+impl Regeneration {
+    pub fn new(per_second: f32) -> Self {
+        Self {
+            per_second,
+            progress: 0.,
+        }
+    }
+}
+
+/// Absorbs damage before [`Health`], recharging once no damage has been taken for a while.
+#[derive(Component, Debug, Clone)]
+pub struct Shield {
+    pub amount: u32,
+    pub total: u32,
+    pub recharge_rate: f32,
+    pub recharge_delay: Timer,
+    progress: f32,
+}
+
+impl Shield {
+    pub fn new(total: u32) -> Self {
+        Self {
+            amount: total,
+            total,
+            recharge_rate: SHIELD_RECHARGE_RATE,
+            recharge_delay: Timer::from_seconds(SHIELD_RECHARGE_DELAY, TimerMode::Once),
+            progress: 0.,
+        }
+    }
+
+    /// Soaks up as much of `amount` as possible, returning how much was absorbed
+    pub fn absorb(&mut self, amount: u32) -> u32 {
+        let absorbed = amount.min(self.amount);
+        self.amount -= absorbed;
+        self.recharge_delay.reset();
+        self.progress = 0.;
+        absorbed
+    }
+}
+
 #[derive(Component, Debug, Default)]
 pub struct HealthBar;
 
 #[derive(Component, Debug)]
 pub struct HealthBarDisplay;
 
+#[derive(Component, Debug)]
+pub struct ShieldBarDisplay;
+
 impl From<&EntityInstance> for Health {
     fn from(value: &EntityInstance) -> Self {
         if let Ok(v) = value.get_int_field("health") {
@@ -71,10 +138,130 @@ pub struct DamageEvent {
     pub amount: u32,
     pub position: Vec3,
     pub receiver: Entity,
+    pub damage_type: DamageType,
+    pub effectiveness: Effectiveness,
+    pub critical: bool,
+    /// How much of the hit was soaked up by a [`Shield`] instead of health
+    pub absorbed: u32,
+    pub source: DamageSource,
+}
+
+/// Restores health to `target`, up to its [`Health::total`].
+#[derive(Debug, Event)]
+pub struct HealEvent {
+    pub target: Entity,
+    pub amount: u32,
+}
+
+/// Player facing options for the floating damage and heal numbers.
+#[derive(Resource, Debug, Clone)]
+pub struct DamageNumbers {
+    pub enabled: bool,
+    /// The oldest numbers are removed once more than this many are on screen
+    pub max_labels: usize,
+}
+
+impl Default for DamageNumbers {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_labels: MAX_DAMAGE_LABELS,
+        }
+    }
+}
+
+/// The damage a single number is showing for one receiver, summed over every hit merged into it.
+#[derive(Debug, Clone)]
+struct DamageTally {
+    receiver: Entity,
+    amount: u32,
+    hits: u32,
+    damage_type: DamageType,
+    effectiveness: Effectiveness,
+    critical: bool,
+    /// Whether every hit so far was fully absorbed by a shield
+    shielded: bool,
+}
+
+impl From<&DamageEvent> for DamageTally {
+    fn from(event: &DamageEvent) -> Self {
+        Self {
+            receiver: event.receiver,
+            amount: event.amount + event.absorbed,
+            hits: 1,
+            damage_type: event.damage_type,
+            effectiveness: event.effectiveness,
+            critical: event.critical,
+            shielded: event.amount == 0,
+        }
+    }
+}
+
+impl DamageTally {
+    /// Folds another hit into the tally, the latest hit decides the type and effectiveness shown
+    fn add(&mut self, other: &DamageTally) {
+        self.amount += other.amount;
+        self.hits += other.hits;
+        self.damage_type = other.damage_type;
+        self.effectiveness = other.effectiveness;
+        self.critical |= other.critical;
+        self.shielded &= other.shielded;
+    }
+
+    fn value(&self) -> String {
+        let amount = self.amount;
+        match self.effectiveness {
+            Effectiveness::Normal => format!("{amount}"),
+            Effectiveness::Weak => format!("{amount}!"),
+            Effectiveness::Resisted => format!("({amount})"),
+        }
+    }
+
+    fn font_size(&self) -> f32 {
+        let base = match self.effectiveness {
+            Effectiveness::Normal => 8.,
+            Effectiveness::Weak => 10.,
+            Effectiveness::Resisted => 6.,
+        };
+        let growth =
+            (1.0 + DAMAGE_LABEL_GROWTH * (self.hits - 1) as f32).min(DAMAGE_LABEL_MAX_GROWTH);
+        if self.critical {
+            base * growth * 1.5
+        } else {
+            base * growth
+        }
+    }
+
+    fn color(&self) -> Color {
+        if self.critical {
+            Color::GOLD
+        } else if self.shielded {
+            SHIELD_COLOR
+        } else {
+            self.damage_type.color()
+        }
+    }
+
+    fn duration(&self) -> f32 {
+        if self.critical {
+            0.6
+        } else {
+            DAMAGE_LABEL_DURATION
+        }
+    }
 }
 
 #[derive(Component, Debug, Default)]
-pub struct DamageDisplay(Timer);
+pub struct DamageDisplay {
+    timer: Timer,
+    velocity: Vec2,
+    /// Set for damage numbers, which keep absorbing hits on the same receiver for a short while
+    tally: Option<DamageTally>,
+}
+
+/// Makes a damage number briefly grow and shrink back, used for critical hits.
+#[derive(Component, Debug, Default)]
+pub struct DamagePop;
 
 #[derive(Bundle, Debug)]
 pub struct DamageDisplayBundle {
@@ -83,20 +270,57 @@ pub struct DamageDisplayBundle {
 }
 
 impl DamageDisplayBundle {
-    fn new(position: Vec3, amount: u32, fonts: &Res<Fonts>) -> Self {
+    fn new(position: Vec3, tally: DamageTally, fonts: &Res<Fonts>) -> Self {
+        let mut bundle = Self::with_text(
+            position,
+            tally.value(),
+            tally.font_size(),
+            tally.color(),
+            tally.duration(),
+            fonts,
+        );
+        bundle.timer.tally = Some(tally);
+        bundle
+    }
+
+    fn heal(position: Vec3, amount: u32, fonts: &Res<Fonts>) -> Self {
+        Self::with_text(
+            position,
+            format!("+{amount}"),
+            8.,
+            Color::GREEN,
+            DAMAGE_LABEL_DURATION,
+            fonts,
+        )
+    }
+
+    fn with_text(
+        position: Vec3,
+        value: String,
+        font_size: f32,
+        color: Color,
+        duration: f32,
+        fonts: &Res<Fonts>,
+    ) -> Self {
         let mut transform = Transform::from_translation(position);
         transform.translation.z = 1000.;
-        let timer = DamageDisplay(Timer::from_seconds(0.25, TimerMode::Once));
+        // Cosmetic only, so this deliberately doesn't draw from the run's RNG
+        let jitter = (random::<f32>() * 2.0 - 1.0) * DAMAGE_LABEL_JITTER;
+        let timer = DamageDisplay {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+            velocity: Vec2::new(jitter, DAMAGE_LABEL_RISE_SPEED),
+            tally: None,
+        };
         Self {
             timer,
             text: Text2dBundle {
                 text: Text {
                     sections: vec![TextSection {
-                        value: format!("{amount}"),
+                        value,
                         style: TextStyle {
                             font: fonts.arcade.clone(),
-                            font_size: 8.,
-                            color: Color::ORANGE_RED,
+                            font_size,
+                            color,
                         },
                     }],
                     ..Default::default()
@@ -138,48 +362,70 @@ impl Health {
     }
 }
 
-fn take_damage<T: Component, E: Component>(
+fn tick_damage_cooldown(
     mut commands: Commands,
-    mut events: EventReader<CollisionEvent>,
-    mut damage_events: EventWriter<DamageEvent>,
-    mut receiver: Query<(&mut Health, &Transform), (With<T>, Without<DamageCooldown>)>,
-    damager: Query<&CollisionDamage, With<E>>,
+    mut query: Query<(Entity, &mut DamageCooldown)>,
+    time: Res<Time>,
 ) {
-    for collision in events.read() {
-        let Ok((mut health, transform)) = receiver.get_mut(collision.entity) else {
-            continue;
-        };
-
-        let Ok(damage) = damager.get(collision.collided_with) else {
-            continue;
-        };
-
-        damage_events.send(DamageEvent {
-            amount: damage.amount,
-            position: transform.translation,
-            receiver: collision.entity,
-        });
+    for (entity, mut cooldown) in query.iter_mut() {
+        cooldown.cooldown.tick(time.delta());
 
-        health.amount = health.amount.saturating_sub(damage.amount);
+        if cooldown.cooldown.just_finished() {
+            commands.entity(entity).remove::<DamageCooldown>();
+        }
+    }
+}
 
-        if let Some(duration) = health.cooldown.as_ref() {
-            commands
-                .entity(collision.entity)
-                .insert(DamageCooldown::new(*duration));
+fn regenerate(
+    mut query: Query<(Entity, &mut Regeneration, &Health)>,
+    mut heal_events: EventWriter<HealEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut regeneration, health) in query.iter_mut() {
+        if health.amount >= health.total {
+            regeneration.progress = 0.;
+            continue;
+        }
+        regeneration.progress += regeneration.per_second * time.delta_seconds();
+        let amount = regeneration.progress.floor();
+        if amount >= 1. {
+            regeneration.progress -= amount;
+            heal_events.send(HealEvent {
+                target: entity,
+                amount: amount as u32,
+            });
         }
     }
 }
 
-fn tick_damage_cooldown(
+fn apply_healing(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut DamageCooldown)>,
-    time: Res<Time>,
+    mut events: EventReader<HealEvent>,
+    mut query: Query<(&mut Health, &Transform)>,
+    settings: Res<DamageNumbers>,
+    fonts: Res<Fonts>,
 ) {
-    for (entity, mut cooldown) in query.iter_mut() {
-        cooldown.cooldown.tick(time.delta());
+    for event in events.read() {
+        let Ok((mut health, transform)) = query.get_mut(event.target) else {
+            continue;
+        };
 
-        if cooldown.cooldown.just_finished() {
-            commands.entity(entity).remove::<DamageCooldown>();
+        // The dead can't be healed
+        if health.amount == 0 {
+            continue;
+        }
+
+        let amount = event.amount.min(health.total.saturating_sub(health.amount));
+        if amount == 0 {
+            continue;
+        }
+        health.amount += amount;
+        if settings.enabled {
+            commands.spawn(DamageDisplayBundle::heal(
+                transform.translation,
+                amount,
+                &fonts,
+            ));
         }
     }
 }
@@ -214,6 +460,19 @@ fn create_health_bars(
                     ..Default::default()
                 },
             ));
+            // Current shield, hidden until there is a shield to show
+            children.spawn((
+                ShieldBarDisplay,
+                MaterialMesh2dBundle {
+                    mesh: meshes
+                        .add(shape::Quad::new(Vec2::new(12.0, 1.0)).into())
+                        .into(),
+                    material: materials.add(ColorMaterial::from(SHIELD_COLOR)),
+                    transform: Transform::from_translation(Vec3::new(0., 11.5, 101.0))
+                        .with_scale(Vec3::new(0., 1., 1.)),
+                    ..Default::default()
+                },
+            ));
             // Missing health
             children.spawn(MaterialMesh2dBundle {
                 mesh: meshes
@@ -244,31 +503,138 @@ fn update_health_bars(
     }
 }
 
-fn display_damage(mut commands: Commands, mut events: EventReader<DamageEvent>, fonts: Res<Fonts>) {
-    for DamageEvent {
-        amount,
-        position,
-        receiver: _,
-    } in events.read()
-    {
-        commands.spawn(DamageDisplayBundle::new(*position, *amount, &fonts));
+fn update_shield_bars(
+    shields: Query<Option<&Shield>, With<HealthBar>>,
+    mut current_shield: Query<(&Parent, &mut Transform), With<ShieldBarDisplay>>,
+) {
+    for (parent, mut transform) in current_shield.iter_mut() {
+        let Ok(shield) = shields.get(parent.get()) else {
+            continue;
+        };
+        let percent = shield
+            .filter(|shield| shield.total > 0)
+            .map_or(0., |shield| shield.amount as f32 / shield.total as f32);
+        transform.translation.x = (1.0 - percent) * -6.;
+        transform.scale.x = percent;
+    }
+}
+
+fn recharge_shields(mut query: Query<(&mut Shield, &Health)>, time: Res<Time>) {
+    for (mut shield, health) in query.iter_mut() {
+        if health.amount == 0 || shield.amount >= shield.total {
+            continue;
+        }
+        if !shield.recharge_delay.tick(time.delta()).finished() {
+            continue;
+        }
+        shield.progress += shield.recharge_rate * time.delta_seconds();
+        let amount = shield.progress.floor();
+        if amount >= 1. {
+            shield.progress -= amount;
+            shield.amount = (shield.amount + amount as u32).min(shield.total);
+        }
+    }
+}
+
+fn display_damage(
+    mut commands: Commands,
+    mut events: EventReader<DamageEvent>,
+    mut displays: Query<(Entity, &mut DamageDisplay, &mut Text)>,
+    settings: Res<DamageNumbers>,
+    fonts: Res<Fonts>,
+) {
+    if !settings.enabled {
+        events.clear();
+        return;
+    }
+
+    // Hits landing on the same frame always share a number
+    let mut tallies: HashMap<Entity, (Vec3, DamageTally)> = HashMap::new();
+    for event in events.read() {
+        let tally = DamageTally::from(event);
+        tallies
+            .entry(event.receiver)
+            .and_modify(|(_, existing)| existing.add(&tally))
+            .or_insert((event.position, tally));
+    }
+
+    for (receiver, (position, tally)) in tallies.into_iter() {
+        let recent = displays.iter_mut().find(|(_, display, _)| {
+            display.timer.elapsed_secs() < DAMAGE_LABEL_MERGE_WINDOW
+                && display
+                    .tally
+                    .as_ref()
+                    .is_some_and(|existing| existing.receiver == receiver)
+        });
+
+        if let Some((entity, mut display, mut text)) = recent {
+            let Some(existing) = display.tally.as_mut() else {
+                continue;
+            };
+            existing.add(&tally);
+            let section = &mut text.sections[0];
+            section.value = existing.value();
+            section.style.font_size = existing.font_size();
+            section.style.color = existing.color();
+            if existing.critical {
+                commands.entity(entity).insert(DamagePop);
+            }
+            // The timer keeps running, so a number only takes hits for the merge window after
+            // it appeared instead of following a constantly hit enemy forever
+            continue;
+        }
+
+        let critical = tally.critical;
+        let mut display = commands.spawn(DamageDisplayBundle::new(position, tally, &fonts));
+        if critical {
+            display.insert(DamagePop);
+        }
+    }
+}
+
+/// Removes the oldest numbers once there are more than [`DamageNumbers::max_labels`]
+fn limit_damage_displays(
+    mut commands: Commands,
+    query: Query<(Entity, &DamageDisplay)>,
+    settings: Res<DamageNumbers>,
+) {
+    let mut displays: Vec<_> = query.iter().collect();
+    if displays.len() <= settings.max_labels {
+        return;
+    }
+    displays.sort_by(|(_, a), (_, b)| b.timer.percent().total_cmp(&a.timer.percent()));
+    let excess = displays.len() - settings.max_labels;
+    for (entity, _) in displays.into_iter().take(excess) {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
 fn tick_damage_display(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut DamageDisplay, &mut Text)>,
+    mut query: Query<(
+        Entity,
+        &mut DamageDisplay,
+        &mut Text,
+        &mut Transform,
+        Option<&DamagePop>,
+    )>,
     time: Res<Time>,
 ) {
-    for (entity, mut display, mut text) in query.iter_mut() {
-        display.0.tick(time.delta());
-        if display.0.just_finished() {
+    for (entity, mut display, mut text, mut transform, pop) in query.iter_mut() {
+        display.timer.tick(time.delta());
+        if display.timer.just_finished() {
             commands.entity(entity).despawn_recursive();
         } else {
+            let step = display.velocity * time.delta_seconds();
+            transform.translation += step.extend(0.);
             text.sections[0]
                 .style
                 .color
-                .set_a(1.0 - display.0.percent().powf(1.5));
+                .set_a(1.0 - display.timer.percent().powf(1.5));
+            if pop.is_some() {
+                let scale = 1.0 + 0.5 * (display.timer.percent() * std::f32::consts::PI).sin();
+                transform.scale = Vec3::splat(scale);
+            }
         }
     }
 }
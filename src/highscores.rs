@@ -0,0 +1,373 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loader::Fonts,
+    input::{Action, ActionState},
+    player::PLAYER_CHARACTER,
+    rng::RunRng,
+    schedule::AppState,
+    stats::RunStats,
+};
+
+/// Bumped whenever [`HighScore`] changes shape, older files are discarded
+const HIGH_SCORE_VERSION: u32 = 1;
+const HIGH_SCORE_FILE: &str = "highscores.ron";
+pub const MAX_HIGH_SCORES: usize = 10;
+const INITIALS_LENGTH: usize = 3;
+/// What the gamepad cycles through for each initial
+const INITIALS_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+/// Saved for players who confirm without entering any initials
+const ANONYMOUS_INITIALS: &str = "???";
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .init_resource::<PendingHighScore>()
+            .add_systems(OnEnter(AppState::GameOver), check_for_high_score)
+            .add_systems(OnExit(AppState::GameOver), cleanup_initials_prompt)
+            .add_systems(Update, enter_initials.run_if(in_state(AppState::GameOver)));
+    }
+}
+
+/// One finished run on the high score table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: u32,
+    /// Seconds survived
+    pub time: f32,
+    pub level: u32,
+    pub kills: u32,
+    pub character: String,
+    /// Seconds since the unix epoch when the run ended
+    pub date: u64,
+    /// The [`RunRng`] seed, so the run can be replayed
+    pub seed: u64,
+}
+
+impl HighScore {
+    fn from_run(stats: &RunStats, seed: u64) -> Self {
+        let time = stats.time.elapsed_secs();
+        Self {
+            initials: String::new(),
+            score: score(stats),
+            time,
+            level: stats.level,
+            kills: stats.kills,
+            character: PLAYER_CHARACTER.to_owned(),
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            seed,
+        }
+    }
+
+    /// The date the run ended as `YYYY-MM-DD`
+    pub fn date_string(&self) -> String {
+        let (year, month, day) = civil_from_days((self.date / 86_400) as i64);
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// Kills are worth the most, surviving and levelling up add a bonus
+fn score(stats: &RunStats) -> u32 {
+    stats.kills * 10 + stats.time.elapsed_secs() as u32 + stats.level.saturating_sub(1) * 50
+}
+
+/// Converts days since the unix epoch to a (year, month, day) date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The file format, versioned so that old or foreign files can be recognised.
+#[derive(Debug, Serialize, Deserialize)]
+struct HighScoreFile {
+    version: u32,
+    entries: Vec<HighScore>,
+}
+
+/// The best runs so far, highest score first.
+#[derive(Resource, Debug, Default)]
+pub struct HighScores {
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "Bevy Survivors")
+            .map(|dirs| dirs.data_dir().join(HIGH_SCORE_FILE))
+    }
+
+    /// Reads the saved table. A missing file starts an empty table, an unreadable one is
+    /// moved aside so it isn't overwritten.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            warn!("No data directory available, high scores won't be saved");
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match ron::from_str::<HighScoreFile>(&contents) {
+            Ok(file) if file.version == HIGH_SCORE_VERSION => {
+                let mut scores = Self {
+                    entries: file.entries,
+                };
+                scores.sort();
+                scores
+            }
+            Ok(file) => {
+                warn!(
+                    "Ignoring high scores saved in unsupported version {}",
+                    file.version
+                );
+                Self::set_aside(&path);
+                Self::default()
+            }
+            Err(error) => {
+                error!(
+                    "Could not read high scores from {}: {error}",
+                    path.display()
+                );
+                Self::set_aside(&path);
+                Self::default()
+            }
+        }
+    }
+
+    fn set_aside(path: &PathBuf) {
+        if let Err(error) = fs::rename(path, path.with_extension("ron.bak")) {
+            error!("Could not back up {}: {error}", path.display());
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        let file = HighScoreFile {
+            version: HIGH_SCORE_VERSION,
+            entries: self.entries.clone(),
+        };
+        let result = ron::ser::to_string_pretty(&file, Default::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+                }
+                fs::write(&path, contents).map_err(|error| error.to_string())
+            });
+        if let Err(error) = result {
+            error!("Could not save high scores to {}: {error}", path.display());
+        }
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    /// Whether a run with this score would make it onto the table
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_HIGH_SCORES
+                || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    pub fn insert(&mut self, entry: HighScore) {
+        self.entries.push(entry);
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.entries.sort_by(|a, b| b.score.cmp(&a.score));
+        self.entries.truncate(MAX_HIGH_SCORES);
+    }
+}
+
+/// A run that made the table, waiting for the player to enter their initials.
+#[derive(Resource, Debug, Default)]
+pub struct PendingHighScore(Option<HighScore>);
+
+impl PendingHighScore {
+    pub fn is_entering(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+#[derive(Component, Debug)]
+struct InitialsPrompt;
+
+#[derive(Component, Debug)]
+struct InitialsText;
+
+/// The instructions under the prompt, hidden once the score is saved
+#[derive(Component, Debug)]
+struct InitialsHint;
+
+fn check_for_high_score(
+    mut commands: Commands,
+    mut pending: ResMut<PendingHighScore>,
+    scores: Res<HighScores>,
+    stats: Res<RunStats>,
+    rng: Res<RunRng>,
+    fonts: Res<Fonts>,
+) {
+    let entry = HighScore::from_run(&stats, rng.seed());
+    if !scores.qualifies(entry.score) {
+        return;
+    }
+
+    let style = |font_size: f32, color: Color| TextStyle {
+        font: fonts.press_start.clone(),
+        font_size,
+        color,
+    };
+    commands
+        .spawn((
+            InitialsPrompt,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    top: Val::Px(20.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(8.),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("NEW HIGH SCORE {}!", entry.score),
+                style(14.0, Color::GOLD),
+            ));
+            parent.spawn((
+                InitialsHint,
+                TextBundle::from_section("ENTER YOUR INITIALS", style(10.0, Color::WHITE)),
+            ));
+            parent.spawn((
+                InitialsText,
+                TextBundle::from_section(initials_display(""), style(24.0, Color::YELLOW)),
+            ));
+            parent.spawn((
+                InitialsHint,
+                TextBundle::from_section(
+                    "D-PAD: UP/DOWN CHANGES A LETTER, RIGHT ADDS ONE",
+                    style(8.0, Color::GRAY),
+                ),
+            ));
+        });
+    pending.0 = Some(entry);
+}
+
+fn initials_display(initials: &str) -> String {
+    format!("{initials:_<INITIALS_LENGTH$}")
+}
+
+/// Letters type the initials, backspace removes one and confirm saves the score.
+/// On a gamepad, up and down change the last letter, right adds a letter and left removes one.
+pub fn enter_initials(
+    mut characters: EventReader<ReceivedCharacter>,
+    input: Res<Input<KeyCode>>,
+    actions: Res<ActionState>,
+    mut pending: ResMut<PendingHighScore>,
+    mut scores: ResMut<HighScores>,
+    mut text: Query<&mut Text, With<InitialsText>>,
+    mut hints: Query<&mut Visibility, With<InitialsHint>>,
+) {
+    let Some(entry) = pending.0.as_mut() else {
+        characters.clear();
+        return;
+    };
+
+    for event in characters.read() {
+        if entry.initials.len() < INITIALS_LENGTH && event.char.is_ascii_alphanumeric() {
+            entry.initials.push(event.char.to_ascii_uppercase());
+        }
+    }
+    if input.just_pressed(KeyCode::Back) || actions.gamepad_just_pressed(Action::Left) {
+        entry.initials.pop();
+    }
+    if actions.gamepad_just_pressed(Action::Right) && entry.initials.len() < INITIALS_LENGTH {
+        entry.initials.push('A');
+    }
+    let step = match (
+        actions.gamepad_just_pressed(Action::Up),
+        actions.gamepad_just_pressed(Action::Down),
+    ) {
+        (true, false) => 1,
+        (false, true) => INITIALS_ALPHABET.len() - 1,
+        _ => 0,
+    };
+    if step > 0 {
+        let last = entry.initials.pop().unwrap_or('A');
+        let index = INITIALS_ALPHABET.find(last).unwrap_or_default();
+        let next = (index + step) % INITIALS_ALPHABET.len();
+        entry
+            .initials
+            .push(INITIALS_ALPHABET.as_bytes()[next] as char);
+    }
+    for mut text in text.iter_mut() {
+        text.sections[0].value = initials_display(&entry.initials);
+    }
+
+    if actions.just_pressed(Action::Confirm) {
+        if entry.initials.is_empty() {
+            entry.initials = ANONYMOUS_INITIALS.to_owned();
+        }
+        if let Some(entry) = pending.0.take() {
+            // The prompt stays up until the screen is left, showing what was saved
+            for mut text in text.iter_mut() {
+                text.sections[0].value = format!("SAVED AS {}", entry.initials);
+            }
+            for mut visibility in hints.iter_mut() {
+                *visibility = Visibility::Hidden;
+            }
+            scores.insert(entry);
+            scores.save();
+        }
+    }
+}
+
+/// Leaving the game over screen with the prompt still up, like clicking a button, saves the
+/// score with whatever initials were entered
+fn cleanup_initials_prompt(
+    mut commands: Commands,
+    mut pending: ResMut<PendingHighScore>,
+    mut scores: ResMut<HighScores>,
+    query: Query<Entity, With<InitialsPrompt>>,
+) {
+    if let Some(mut entry) = pending.0.take() {
+        if entry.initials.is_empty() {
+            entry.initials = ANONYMOUS_INITIALS.to_owned();
+        }
+        scores.insert(entry);
+        scores.save();
+    }
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,397 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset_loader::{Fonts, SpriteAssets},
+    attached::{Aura, OrbitingBlades},
+    beam::LightningRod,
+    enemies::Enemy,
+    experience::Experience,
+    health::Health,
+    levels::ActiveSpawnList,
+    melee::Sword,
+    player::{Player, Weapon, WeaponType},
+    schedule::{AppState, InGame},
+    stats::RunStats,
+};
+
+const HUD_FONT_SIZE: f32 = 20.;
+const HUD_TEXT_COLOR: Color = Color::WHITE;
+const BAR_BACKGROUND: Color = Color::rgba(0., 0., 0., 0.6);
+const XP_BAR_COLOR: Color = Color::rgb(0.3, 0.6, 1.0);
+const HEALTH_BAR_COLOR: Color = Color::rgb(0.8, 0.1, 0.1);
+const WEAPON_ICON_SIZE: f32 = 32.;
+const COOLDOWN_OVERLAY: Color = Color::rgba(0., 0., 0., 0.6);
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), setup_hud)
+            .add_systems(OnExit(AppState::InGame), cleanup_hud)
+            .add_systems(
+                Update,
+                (
+                    update_timer,
+                    update_kills,
+                    update_experience,
+                    update_health,
+                    update_remaining_enemies,
+                    update_weapon_icons,
+                    update_weapon_cooldowns.after(update_weapon_icons),
+                )
+                    .in_set(InGame::EntityUpdates),
+            );
+    }
+}
+
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct Hud;
+
+#[derive(Component, Debug)]
+struct HudTimer;
+
+#[derive(Component, Debug)]
+struct HudKills;
+
+#[derive(Component, Debug)]
+struct HudLevel;
+
+#[derive(Component, Debug)]
+struct HudExperienceFill;
+
+#[derive(Component, Debug)]
+struct HudHealth;
+
+#[derive(Component, Debug)]
+struct HudHealthFill;
+
+#[derive(Component, Debug)]
+struct HudEnemies;
+
+/// Holds one icon per equipped weapon
+#[derive(Component, Debug)]
+struct HudWeapons;
+
+#[derive(Component, Debug)]
+struct HudWeaponIcon(WeaponType);
+
+/// Darkens the part of a weapon icon that is still cooling down
+#[derive(Component, Debug)]
+struct HudCooldown;
+
+fn hud_text(value: impl Into<String>, fonts: &Fonts) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font: fonts.arcade.clone(),
+            font_size: HUD_FONT_SIZE,
+            color: HUD_TEXT_COLOR,
+        },
+    )
+}
+
+/// A bar with a `fill` child whose width follows a percentage
+fn spawn_bar(
+    parent: &mut ChildBuilder,
+    width: Val,
+    height: f32,
+    color: Color,
+    fill: impl Component,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width,
+                height: Val::Px(height),
+                ..default()
+            },
+            background_color: BAR_BACKGROUND.into(),
+            ..default()
+        })
+        .with_children(|bar| {
+            bar.spawn((
+                fill,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn setup_hud(mut commands: Commands, fonts: Res<Fonts>) {
+    commands
+        .spawn((
+            Hud,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|root| {
+            // Top: experience bar, then level, timer and kills
+            root.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|top| {
+                spawn_bar(top, Val::Percent(100.), 8., XP_BAR_COLOR, HudExperienceFill);
+                top.spawn(NodeBundle {
+                    style: Style {
+                        justify_content: JustifyContent::SpaceBetween,
+                        padding: UiRect::all(Val::Px(8.)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn((HudLevel, hud_text("LV 1", &fonts)));
+                    row.spawn((HudTimer, hud_text("00:00", &fonts)));
+                    row.spawn((HudKills, hud_text("KILLS 0", &fonts)));
+                });
+            });
+
+            // Bottom: health and weapons on the left, remaining enemies on the right
+            root.spawn(NodeBundle {
+                style: Style {
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::FlexEnd,
+                    padding: UiRect::all(Val::Px(8.)),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|bottom| {
+                bottom
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(4.),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|left| {
+                        left.spawn((
+                            HudWeapons,
+                            NodeBundle {
+                                style: Style {
+                                    column_gap: Val::Px(4.),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                        ));
+                        left.spawn((HudHealth, hud_text("HP", &fonts)));
+                        spawn_bar(left, Val::Px(120.), 6., HEALTH_BAR_COLOR, HudHealthFill);
+                    });
+                bottom.spawn((HudEnemies, hud_text("ENEMIES 0", &fonts)));
+            });
+        });
+}
+
+fn cleanup_hud(mut commands: Commands, query: Query<Entity, With<Hud>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_timer(mut query: Query<&mut Text, With<HudTimer>>, stats: Res<RunStats>) {
+    let seconds = stats.time.elapsed_secs() as u32;
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+    }
+}
+
+/// Only rewrites the label when the count changes, since the run time changes [`RunStats`]
+/// every frame
+fn update_kills(
+    mut query: Query<(&mut Text, Ref<HudKills>)>,
+    stats: Res<RunStats>,
+    mut shown: Local<Option<u32>>,
+) {
+    let changed = *shown != Some(stats.kills);
+    *shown = Some(stats.kills);
+    for (mut text, label) in query.iter_mut() {
+        // A fresh HUD needs the count even if it's the same as the last run ended on
+        if changed || label.is_added() {
+            text.sections[0].value = format!("KILLS {}", stats.kills);
+        }
+    }
+}
+
+fn update_experience(
+    player: Query<&Experience, (With<Player>, Changed<Experience>)>,
+    mut level: Query<&mut Text, With<HudLevel>>,
+    mut fill: Query<&mut Style, With<HudExperienceFill>>,
+) {
+    let Ok(experience) = player.get_single() else {
+        return;
+    };
+    for mut text in level.iter_mut() {
+        text.sections[0].value = format!("LV {}", experience.level);
+    }
+    for mut style in fill.iter_mut() {
+        style.width = Val::Percent(experience.percent() * 100.);
+    }
+}
+
+fn update_health(
+    player: Query<&Health, (With<Player>, Changed<Health>)>,
+    mut label: Query<&mut Text, With<HudHealth>>,
+    mut fill: Query<&mut Style, With<HudHealthFill>>,
+) {
+    let Ok(health) = player.get_single() else {
+        return;
+    };
+    for mut text in label.iter_mut() {
+        text.sections[0].value = format!("HP {}/{}", health.amount, health.total);
+    }
+    let percent = if health.total > 0 {
+        health.amount as f32 / health.total as f32
+    } else {
+        0.
+    };
+    for mut style in fill.iter_mut() {
+        style.width = Val::Percent(percent * 100.);
+    }
+}
+
+/// Counts both the enemies still waiting to spawn and the ones already alive
+fn update_remaining_enemies(
+    mut query: Query<&mut Text, With<HudEnemies>>,
+    spawns: Res<ActiveSpawnList>,
+    enemies: Query<(), With<Enemy>>,
+) {
+    let remaining = spawns.remaining() + enemies.iter().count() as u32;
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!("ENEMIES {remaining}");
+    }
+}
+
+/// Every weapon the player could have equipped
+type EquippedWeapons<'a> = (
+    Option<&'a Weapon>,
+    Option<&'a OrbitingBlades>,
+    Option<&'a Aura>,
+    Option<&'a Sword>,
+    Option<&'a LightningRod>,
+);
+
+/// The player's weapons in HUD order, with the fraction of each one's cooldown left.
+/// Orbiting blades are always hitting, so they never show a cooldown.
+fn equipped_weapons((weapon, blades, aura, sword, rod): EquippedWeapons) -> Vec<(WeaponType, f32)> {
+    let remaining = |cooldown: &Timer| 1.0 - cooldown.percent();
+    [
+        weapon.map(|weapon| (weapon.kind(), remaining(weapon.cooldown()))),
+        blades.map(|_| (WeaponType::OrbitingBlades, 0.)),
+        aura.map(|aura| (WeaponType::HolyAura, remaining(aura.cooldown()))),
+        sword.map(|sword| (WeaponType::Sword, remaining(sword.cooldown()))),
+        rod.map(|rod| (WeaponType::LightningRod, remaining(rod.cooldown()))),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Rebuilds the weapon icons whenever the equipped weapons change
+fn update_weapon_icons(
+    mut commands: Commands,
+    containers: Query<(Entity, Option<&Children>), With<HudWeapons>>,
+    icons: Query<&HudWeaponIcon>,
+    players: Query<EquippedWeapons, With<Player>>,
+    sprite_assets: Res<SpriteAssets>,
+) {
+    let kinds: Vec<WeaponType> = players
+        .iter()
+        .flat_map(equipped_weapons)
+        .map(|(kind, _)| kind)
+        .collect();
+    for (container, children) in containers.iter() {
+        let shown: Vec<WeaponType> = children
+            .iter()
+            .flat_map(|children| children.iter())
+            .filter_map(|child| icons.get(*child).ok())
+            .map(|icon| icon.0)
+            .collect();
+        if shown == kinds {
+            continue;
+        }
+
+        let mut container = commands.entity(container);
+        container.despawn_descendants();
+        container.with_children(|row| {
+            for kind in kinds.iter() {
+                row.spawn((
+                    HudWeaponIcon(*kind),
+                    AtlasImageBundle {
+                        style: Style {
+                            width: Val::Px(WEAPON_ICON_SIZE),
+                            height: Val::Px(WEAPON_ICON_SIZE),
+                            ..default()
+                        },
+                        background_color: BAR_BACKGROUND.into(),
+                        texture_atlas: sprite_assets.tiles.clone(),
+                        texture_atlas_image: UiTextureAtlasImage {
+                            index: kind.sprite_index(),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .with_children(|icon| {
+                    icon.spawn((
+                        HudCooldown,
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                bottom: Val::Px(0.),
+                                width: Val::Percent(100.),
+                                height: Val::Percent(0.),
+                                ..default()
+                            },
+                            background_color: COOLDOWN_OVERLAY.into(),
+                            ..default()
+                        },
+                    ));
+                });
+            }
+        });
+    }
+}
+
+/// Sweeps the overlay down as each weapon's cooldown runs out
+fn update_weapon_cooldowns(
+    players: Query<EquippedWeapons, With<Player>>,
+    containers: Query<&Children, With<HudWeapons>>,
+    icons: Query<&Children, With<HudWeaponIcon>>,
+    mut overlays: Query<&mut Style, With<HudCooldown>>,
+) {
+    let weapons: Vec<(WeaponType, f32)> = players.iter().flat_map(equipped_weapons).collect();
+    for slots in containers.iter() {
+        for (slot, (_, remaining)) in slots.iter().zip(weapons.iter()) {
+            let Ok(children) = icons.get(*slot) else {
+                continue;
+            };
+            for child in children.iter() {
+                if let Ok(mut style) = overlays.get_mut(*child) {
+                    style.height = Val::Percent(remaining * 100.);
+                }
+            }
+        }
+    }
+}
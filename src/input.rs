@@ -0,0 +1,287 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+/// Stick deflection past which the stick also counts as a pressed direction, for menus
+const STICK_PRESS_THRESHOLD: f32 = 0.5;
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionState>()
+            .add_systems(PreUpdate, update_action_state.after(InputSystem));
+    }
+}
+
+/// Everything the game reacts to, independent of the key or button that triggered it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+    Pause,
+    Dash,
+}
+
+pub const ACTIONS: [Action; 8] = [
+    Action::Up,
+    Action::Down,
+    Action::Left,
+    Action::Right,
+    Action::Confirm,
+    Action::Back,
+    Action::Pause,
+    Action::Dash,
+];
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "UP",
+            Action::Down => "DOWN",
+            Action::Left => "LEFT",
+            Action::Right => "RIGHT",
+            Action::Confirm => "CONFIRM",
+            Action::Back => "BACK",
+            Action::Pause => "PAUSE",
+            Action::Dash => "DASH",
+        }
+    }
+}
+
+/// Which keys and gamepad buttons trigger each [`Action`]. Saved as part of [`Settings`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+    pub keyboard: HashMap<Action, Vec<KeyCode>>,
+    pub gamepad: HashMap<Action, Vec<GamepadButtonType>>,
+    /// Left stick deflection below this is ignored
+    pub stick_deadzone: f32,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let keyboard = HashMap::from([
+            (Action::Up, vec![KeyCode::W, KeyCode::Up]),
+            (Action::Down, vec![KeyCode::S, KeyCode::Down]),
+            (Action::Left, vec![KeyCode::A, KeyCode::Left]),
+            (Action::Right, vec![KeyCode::D, KeyCode::Right]),
+            (Action::Confirm, vec![KeyCode::Return, KeyCode::Space]),
+            (Action::Back, vec![KeyCode::Escape, KeyCode::Back]),
+            (Action::Pause, vec![KeyCode::Escape, KeyCode::P]),
+            (Action::Dash, vec![KeyCode::ShiftLeft]),
+        ]);
+        let gamepad = HashMap::from([
+            (Action::Up, vec![GamepadButtonType::DPadUp]),
+            (Action::Down, vec![GamepadButtonType::DPadDown]),
+            (Action::Left, vec![GamepadButtonType::DPadLeft]),
+            (Action::Right, vec![GamepadButtonType::DPadRight]),
+            (Action::Confirm, vec![GamepadButtonType::South]),
+            (Action::Back, vec![GamepadButtonType::East]),
+            (Action::Pause, vec![GamepadButtonType::Start]),
+            (Action::Dash, vec![GamepadButtonType::RightTrigger]),
+        ]);
+        Self {
+            keyboard,
+            gamepad,
+            stick_deadzone: 0.2,
+        }
+    }
+}
+
+impl InputBindings {
+    /// Makes `key` the only key bound to `action`
+    pub fn bind_key(&mut self, action: Action, key: KeyCode) {
+        self.keyboard.insert(action, vec![key]);
+    }
+
+    /// Makes `button` the only gamepad button bound to `action`
+    pub fn bind_button(&mut self, action: Action, button: GamepadButtonType) {
+        self.gamepad.insert(action, vec![button]);
+    }
+
+    /// The other actions sharing a key or button with `action`. Back and Pause share Escape on
+    /// purpose, since one is only used in menus and the other only in game.
+    pub fn conflicts(&self, action: Action) -> Vec<Action> {
+        let shared = |other: Action| {
+            let mut keys = self.keyboard.get(&action).into_iter().flatten();
+            let mut buttons = self.gamepad.get(&action).into_iter().flatten();
+            keys.any(|key| {
+                self.keyboard
+                    .get(&other)
+                    .is_some_and(|other_keys| other_keys.contains(key))
+            }) || buttons.any(|button| {
+                self.gamepad
+                    .get(&other)
+                    .is_some_and(|other_buttons| other_buttons.contains(button))
+            })
+        };
+        ACTIONS
+            .into_iter()
+            .filter(|other| *other != action)
+            .filter(|other| {
+                !matches!(
+                    (action, *other),
+                    (Action::Back, Action::Pause) | (Action::Pause, Action::Back)
+                )
+            })
+            .filter(|other| shared(*other))
+            .collect()
+    }
+
+    /// A short description of everything bound to `action`, for the controls screen
+    pub fn describe(&self, action: Action) -> String {
+        let keys = self.keyboard.get(&action).into_iter().flatten();
+        let buttons = self.gamepad.get(&action).into_iter().flatten();
+        keys.map(|key| format!("{key:?}"))
+            .chain(buttons.map(|button| format!("{button:?}")))
+            .collect::<Vec<_>>()
+            .join(" / ")
+            .to_uppercase()
+    }
+}
+
+/// The state of every [`Action`] this frame, from all keyboards and gamepads.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    previous: HashSet<Action>,
+    /// The pressed actions held on a gamepad, rather than only on the keyboard
+    pressed_on_gamepad: HashSet<Action>,
+    movement: Vec2,
+    aim: Vec2,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action) && !self.previous.contains(&action)
+    }
+
+    /// Like [`Self::just_pressed`], but only for presses from a gamepad. Used where keys would
+    /// clash with typing.
+    pub fn gamepad_just_pressed(&self, action: Action) -> bool {
+        self.pressed_on_gamepad.contains(&action) && !self.previous.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        !self.pressed.contains(&action) && self.previous.contains(&action)
+    }
+
+    /// The direction to move in, with a length of at most 1.0.
+    /// Analog sticks give partial lengths, digital inputs always give full ones.
+    pub fn movement(&self) -> Vec2 {
+        self.movement
+    }
+
+    /// Where the right stick points, with a length of at most 1.0. Zero while it's centered.
+    pub fn aim(&self) -> Vec2 {
+        self.aim
+    }
+}
+
+fn update_action_state(
+    mut state: ResMut<ActionState>,
+    settings: Res<Settings>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+) {
+    let bindings = &settings.bindings;
+    let state = &mut *state;
+    state.previous = std::mem::take(&mut state.pressed);
+    state.pressed_on_gamepad.clear();
+
+    for action in ACTIONS {
+        let key_pressed = bindings
+            .keyboard
+            .get(&action)
+            .is_some_and(|keys_bound| keys.any_pressed(keys_bound.iter().copied()));
+        let button_pressed = bindings.gamepad.get(&action).is_some_and(|bound| {
+            gamepads.iter().any(|gamepad| {
+                bound
+                    .iter()
+                    .any(|button| buttons.pressed(GamepadButton::new(gamepad, *button)))
+            })
+        });
+        if key_pressed || button_pressed {
+            state.pressed.insert(action);
+        }
+        if button_pressed {
+            state.pressed_on_gamepad.insert(action);
+        }
+    }
+
+    let digital = Vec2::new(
+        axis(state.pressed(Action::Left), state.pressed(Action::Right)),
+        axis(state.pressed(Action::Down), state.pressed(Action::Up)),
+    );
+
+    let stick = stick_position(
+        &axes,
+        &gamepads,
+        GamepadAxisType::LeftStickX,
+        GamepadAxisType::LeftStickY,
+        bindings.stick_deadzone,
+    );
+    state.aim = stick_position(
+        &axes,
+        &gamepads,
+        GamepadAxisType::RightStickX,
+        GamepadAxisType::RightStickY,
+        bindings.stick_deadzone,
+    )
+    .clamp_length_max(1.0);
+
+    state.movement = (digital + stick).clamp_length_max(1.0);
+
+    for (deflection, action) in [
+        (stick.y, Action::Up),
+        (-stick.y, Action::Down),
+        (-stick.x, Action::Left),
+        (stick.x, Action::Right),
+    ] {
+        if deflection > STICK_PRESS_THRESHOLD {
+            state.pressed.insert(action);
+            state.pressed_on_gamepad.insert(action);
+        }
+    }
+}
+
+/// The combined position of one stick on every gamepad
+fn stick_position(
+    axes: &Axis<GamepadAxis>,
+    gamepads: &Gamepads,
+    x_axis: GamepadAxisType,
+    y_axis: GamepadAxisType,
+    deadzone: f32,
+) -> Vec2 {
+    let mut stick = Vec2::ZERO;
+    for gamepad in gamepads.iter() {
+        let x = axes.get(GamepadAxis::new(gamepad, x_axis));
+        let y = axes.get(GamepadAxis::new(gamepad, y_axis));
+        stick += Vec2::new(x.unwrap_or_default(), y.unwrap_or_default());
+    }
+    // Radial deadzone, rescaled so the stick still starts from zero at its edge
+    let length = stick.length();
+    if length > deadzone {
+        stick / length * ((length - deadzone) / (1. - deadzone))
+    } else {
+        Vec2::ZERO
+    }
+}
+
+fn axis(negative: bool, positive: bool) -> f32 {
+    (positive as i32 - negative as i32) as f32
+}
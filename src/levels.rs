@@ -3,7 +3,7 @@ use std::collections::{HashMap, HashSet};
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
-use crate::{collision::Collider, schedule::AppState};
+use crate::{collision::Collider, enemies::EnemyRegistry, schedule::AppState};
 
 pub struct LevelsPlugin;
 
@@ -40,6 +40,11 @@ impl ActiveSpawnList {
         }
         None
     }
+
+    /// How many enemies are still waiting to be spawned
+    pub fn remaining(&self) -> u32 {
+        self.0.iter().map(|(_, count)| (*count).max(0) as u32).sum()
+    }
 }
 
 impl Plugin for LevelsPlugin {
@@ -116,6 +121,7 @@ fn preload_spawn_spots(
 fn populate_active_spawns(
     mut level_events: EventReader<LevelEvent>,
     mut active_spawns: ResMut<ActiveSpawnList>,
+    registry: Res<EnemyRegistry>,
     ldtk_projects: Query<&Handle<LdtkProject>>,
     ldtk_project_assets: Res<Assets<LdtkProject>>,
 ) {
@@ -159,7 +165,20 @@ fn populate_active_spawns(
                 "Got spawns {names:?} => {counts:?} for level {}",
                 level_iid.as_str()
             );
-            active_spawns.0 = names.into_iter().zip(counts.into_iter()).collect();
+            active_spawns.0 = names
+                .into_iter()
+                .zip(counts)
+                .filter(|(name, _)| {
+                    let known = registry.contains(name);
+                    if !known {
+                        error!(
+                            "Unknown enemy type {name:?} in level {}",
+                            level_iid.as_str()
+                        );
+                    }
+                    known
+                })
+                .collect();
         }
     }
 }
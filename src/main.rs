@@ -1,35 +1,57 @@
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
+mod aim;
 mod asset_loader;
+mod attached;
+mod beam;
 mod camera;
 mod collision;
 mod combat;
+mod damage;
 mod enemies;
+mod experience;
+mod flash;
 mod health;
+mod highscores;
+mod hud;
+mod input;
 mod levels;
+mod melee;
 mod menu;
 mod movement;
+mod options;
+mod pickups;
 mod player;
+mod projectiles;
+mod rng;
 mod schedule;
+mod settings;
+mod stats;
+mod status;
 
 fn main() {
+    // Read before the window is created so it opens with the saved mode and size
+    let (settings, settings_error) = match settings::Settings::load() {
+        Ok(settings) => (settings, None),
+        Err(error) => (Default::default(), Some(error)),
+    };
+
     App::new()
         // Bevy built-ins
         .insert_resource(ClearColor(Color::rgb(0.1, 0., 0.15)))
         .add_plugins(DefaultPlugins.set(
             WindowPlugin {
-                primary_window: Some(Window {
-                    title: "Bevy Survivors".into(),
-                    resolution: (800., 600.).into(),
-                    ..Default::default()
-                }),
+                primary_window: Some(settings.window()),
                 ..Default::default()
             }).set(ImagePlugin::default_nearest())
         )
+        .insert_resource(settings)
         // Our plugins
         .add_plugins(LdtkPlugin)
         .add_plugins(schedule::SchedulePlugin)
+        .add_plugins(input::InputPlugin)
+        .add_plugins(rng::RngPlugin)
         .add_plugins(camera::CameraPlugin)
         .add_plugins(asset_loader::AssetLoaderPlugin)
         .add_plugins(menu::MenuPlugin)
@@ -37,9 +59,26 @@ fn main() {
         .add_plugins(movement::MovementPlugin)
         .add_plugins(enemies::EnemiesPlugin)
         .add_plugins(player::PlayerPlugin)
+        .add_plugins(aim::AimPlugin)
+        .add_plugins(attached::AttachedWeaponsPlugin)
+        .add_plugins(melee::MeleePlugin)
+        .add_plugins(beam::BeamPlugin)
+        .add_plugins(projectiles::ProjectilesPlugin)
         .add_plugins(collision::CollisionPlugin)
         .add_plugins(health::HealthPlugin)
+        .add_plugins(damage::DamagePlugin)
         .add_plugins(combat::CombatPlugin)
+        .add_plugins(status::StatusPlugin)
+        .add_plugins(flash::FlashPlugin)
+        .add_plugins(pickups::PickupsPlugin)
+        .add_plugins(experience::ExperiencePlugin)
+        .add_plugins(stats::StatsPlugin)
+        .add_plugins(hud::HudPlugin)
+        .add_plugins(highscores::HighScoresPlugin)
+        .add_plugins(settings::SettingsPlugin {
+            load_error: settings_error,
+        })
+        .add_plugins(options::OptionsPlugin)
         .run();
 }
 
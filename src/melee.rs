@@ -0,0 +1,196 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    asset_loader::SpriteAssets,
+    collision::Collider,
+    damage::{CritStats, DamagePipeline, DamageRequest, DamageRoll, DamageSource, DamageType},
+    enemies::Enemy,
+    movement::{rotation_from_direction, Facing},
+    player::{add_or_level_up, LeveledWeapon, Player, WeaponType},
+    schedule::InGame,
+};
+
+const SWORD_COOLDOWN: f32 = 1.2;
+/// Seconds a swing takes to sweep its whole arc
+const SWING_DURATION: f32 = 0.2;
+const SWING_VARIANCE: f32 = 0.2;
+
+pub struct MeleePlugin;
+
+impl Plugin for MeleePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            add_or_level_up::<Sword>.in_set(InGame::EntityUpdates),
+        )
+        .add_systems(Update, start_swings.in_set(InGame::UserInput))
+        .add_systems(Update, sweep_swings.in_set(DamagePipeline::Request));
+    }
+}
+
+/// Swings at everything in an arc in front of the player.
+#[derive(Component, Debug, Clone)]
+pub struct Sword {
+    pub level: u32,
+    cooldown: Timer,
+}
+
+impl Sword {
+    pub fn cooldown(&self) -> &Timer {
+        &self.cooldown
+    }
+
+    pub fn range(&self) -> f32 {
+        24. + 4. * (self.level - 1) as f32
+    }
+
+    /// The angle swept by each swing, in radians
+    pub fn arc(&self) -> f32 {
+        (100. + 20. * (self.level - 1) as f32).to_radians()
+    }
+
+    pub fn damage(&self) -> u32 {
+        6 + 2 * self.level
+    }
+}
+
+impl LeveledWeapon for Sword {
+    const KIND: WeaponType = WeaponType::Sword;
+
+    fn new() -> Self {
+        Self {
+            level: 1,
+            cooldown: Timer::from_seconds(SWORD_COOLDOWN, TimerMode::Repeating),
+        }
+    }
+
+    fn level_mut(&mut self) -> &mut u32 {
+        &mut self.level
+    }
+}
+
+/// A swing in progress, spawned as a child of the player so it follows them.
+/// It sweeps counterclockwise from `start` to `start + arc`.
+#[derive(Component, Debug)]
+struct Swing {
+    start: f32,
+    arc: f32,
+    range: f32,
+    damage: u32,
+    timer: Timer,
+    /// Every enemy is hit at most once per swing
+    hit: HashSet<Entity>,
+}
+
+impl Swing {
+    /// Whether `point`, relative to the player, is in the part of the arc swept so far
+    fn contains(&self, point: Vec2) -> bool {
+        if point.length() <= f32::EPSILON {
+            return true;
+        }
+        let swept = self.arc * self.timer.percent();
+        let angle = point.y.atan2(point.x);
+        (angle - self.start).rem_euclid(std::f32::consts::TAU) <= swept
+    }
+}
+
+fn start_swings(
+    mut commands: Commands,
+    mut players: Query<(Entity, &mut Sword, &Facing, &CritStats), With<Player>>,
+    sprite_assets: Res<SpriteAssets>,
+    time: Res<Time>,
+) {
+    for (player, mut sword, facing, crit) in players.iter_mut() {
+        if !sword.cooldown.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let facing_angle = facing.value.y.atan2(facing.value.x);
+        let arc = sword.arc();
+        commands.entity(player).with_children(|parent| {
+            parent.spawn((
+                Swing {
+                    start: facing_angle - arc / 2.,
+                    arc,
+                    range: sword.range(),
+                    damage: sword.damage(),
+                    timer: Timer::from_seconds(SWING_DURATION, TimerMode::Once),
+                    hit: HashSet::new(),
+                },
+                SpriteSheetBundle {
+                    texture_atlas: sprite_assets.tiles.clone(),
+                    sprite: TextureAtlasSprite::new(WeaponType::Sword.sprite_index()),
+                    // Placed by `sweep_swings` before it's drawn
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                DamageSource {
+                    attacker: Some(player),
+                    weapon: Some(WeaponType::Sword),
+                },
+                DamageRoll {
+                    crit: *crit,
+                    variance: SWING_VARIANCE,
+                },
+            ));
+        });
+    }
+}
+
+/// Moves each swing along its arc, hitting every enemy the blade has passed over
+fn sweep_swings(
+    mut commands: Commands,
+    mut swings: Query<(
+        Entity,
+        &Parent,
+        &mut Swing,
+        &mut Transform,
+        &mut Visibility,
+        &DamageSource,
+        &DamageRoll,
+    )>,
+    players: Query<&Transform, (With<Player>, Without<Swing>)>,
+    enemies: Query<(Entity, &Transform, &Collider), (With<Enemy>, Without<Swing>)>,
+    mut requests: EventWriter<DamageRequest>,
+    time: Res<Time>,
+) {
+    for (entity, parent, mut swing, mut transform, mut visibility, source, roll) in
+        swings.iter_mut()
+    {
+        let Ok(player_transform) = players.get(parent.get()) else {
+            continue;
+        };
+        swing.timer.tick(time.delta());
+
+        let center = player_transform.translation.truncate();
+        for (enemy, enemy_transform, collider) in enemies.iter() {
+            if swing.hit.contains(&enemy) {
+                continue;
+            }
+            // Reaches the edge of the enemy, but aims for its middle
+            let rect = collider.to_rect_at(enemy_transform);
+            let closest = center.clamp(rect.min, rect.max);
+            if closest.distance(center) > swing.range || !swing.contains(rect.center() - center) {
+                continue;
+            }
+            swing.hit.insert(enemy);
+            requests.send(DamageRequest {
+                target: enemy,
+                amount: swing.damage,
+                damage_type: DamageType::Physical,
+                source: *source,
+                roll: *roll,
+            });
+        }
+
+        if swing.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let angle = swing.start + swing.arc * swing.timer.percent();
+        let direction = Vec2::from_angle(angle).extend(0.);
+        transform.translation = (direction * swing.range * 0.6).truncate().extend(1.);
+        transform.rotation = rotation_from_direction(direction);
+        *visibility = Visibility::Inherited;
+    }
+}
@@ -1,6 +1,16 @@
-use bevy::prelude::*;
+use bevy::{app::AppExit, prelude::*};
 
-use crate::{asset_loader::Fonts, schedule::AppState};
+use crate::{
+    asset_loader::Fonts,
+    highscores::{enter_initials, HighScores, PendingHighScore},
+    input::{Action, ActionState},
+    schedule::{AppState, OptionsState, PauseState},
+    stats::RunStats,
+};
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.1, 0.2);
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.25, 0.18, 0.32);
+const PRESSED_BUTTON_COLOR: Color = Color::rgb(0.35, 0.25, 0.45);
 
 pub struct MenuPlugin;
 
@@ -9,13 +19,30 @@ impl Plugin for MenuPlugin {
         // 1. setup the menu
         // 2. remove the menu items on exit
         // 3. handle user input
-        app.add_systems(OnEnter(AppState::Menu), setup_menu)
+        app.add_event::<MenuSelected>()
+            .add_systems(OnEnter(AppState::Menu), setup_menu)
             .add_systems(OnExit(AppState::Menu), cleanup_menu)
+            .add_systems(OnEnter(AppState::HighScores), setup_high_scores)
+            .add_systems(OnExit(AppState::HighScores), cleanup_high_scores)
             .add_systems(
                 Update,
-                (handle_menu_input, blink_text)
-                    .run_if(in_state(AppState::Menu).or_else(in_state(AppState::GameOver))),
+                (
+                    // A confirm that saves the initials shouldn't also press a button
+                    navigate_menus
+                        .before(enter_initials)
+                        .run_if(in_state(OptionsState::Closed)),
+                    handle_menu_selection,
+                    style_menu_buttons,
+                )
+                    .chain(),
             )
+            .add_systems(
+                Update,
+                handle_pause_input
+                    .run_if(in_state(AppState::InGame).and_then(in_state(OptionsState::Closed))),
+            )
+            .add_systems(OnEnter(PauseState::Paused), setup_pause_menu)
+            .add_systems(OnExit(PauseState::Paused), cleanup_pause_menu)
             .add_systems(OnEnter(AppState::GameOver), setup_end_screen)
             .add_systems(OnExit(AppState::GameOver), cleanup_end_screen);
     }
@@ -27,12 +54,219 @@ pub struct Menu;
 #[derive(Component, Debug, Default, Clone, Copy)]
 pub struct GameOver;
 
-#[derive(Component, Debug, Clone)]
-pub struct Blink(Timer);
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct PauseMenu;
+
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct HighScoreScreen;
+
+/// What happens when a [`MenuButton`] is activated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuAction {
+    Play,
+    Options,
+    HighScores,
+    Quit,
+    Resume,
+    MainMenu,
+}
+
+/// A vertical list of [`MenuButton`]s. Up/down or the mouse moves the focus, confirm or a click
+/// activates the focused button and back activates `back`, if the list has one.
+#[derive(Component, Debug, Default)]
+pub struct MenuList {
+    pub focused: usize,
+    pub back: Option<MenuAction>,
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct MenuButton {
+    pub index: usize,
+    pub action: MenuAction,
+}
+
+/// Sent when a [`MenuButton`] is activated, or back is pressed on a list with a back action
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MenuSelected(pub MenuAction);
+
+/// Spawns a [`MenuList`] with one button per entry, the first one focused
+pub fn spawn_menu_list(
+    parent: &mut ChildBuilder,
+    font: Handle<Font>,
+    entries: &[(&str, MenuAction)],
+    back: Option<MenuAction>,
+) {
+    parent
+        .spawn((
+            MenuList { focused: 0, back },
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Stretch,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|list| {
+            for (index, (label, action)) in entries.iter().enumerate() {
+                list.spawn((
+                    MenuButton {
+                        index,
+                        action: *action,
+                    },
+                    ButtonBundle {
+                        style: Style {
+                            min_width: Val::Px(240.0),
+                            padding: UiRect::all(Val::Px(8.0)),
+                            border: UiRect::all(Val::Px(2.0)),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        background_color: BUTTON_COLOR.into(),
+                        ..default()
+                    },
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section(
+                        *label,
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 14.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+            }
+        });
+}
+
+fn navigate_menus(
+    actions: Res<ActionState>,
+    pending_high_score: Res<PendingHighScore>,
+    mut lists: Query<(&mut MenuList, &Children)>,
+    buttons: Query<(&MenuButton, Ref<Interaction>)>,
+    mut selected: EventWriter<MenuSelected>,
+) {
+    // Typing initials uses the same keys as the menu, but the mouse still works
+    let typing = pending_high_score.is_entering();
+
+    for (mut list, children) in lists.iter_mut() {
+        let count = children.len();
+        if count == 0 {
+            continue;
+        }
+
+        for (button, interaction) in buttons.iter_many(children) {
+            if !interaction.is_changed() {
+                continue;
+            }
+            match *interaction {
+                Interaction::Hovered => list.focused = button.index,
+                Interaction::Pressed => {
+                    list.focused = button.index;
+                    selected.send(MenuSelected(button.action));
+                }
+                Interaction::None => (),
+            }
+        }
+        if typing {
+            continue;
+        }
+
+        if actions.just_pressed(Action::Up) {
+            list.focused = (list.focused + count - 1) % count;
+        }
+        if actions.just_pressed(Action::Down) {
+            list.focused = (list.focused + 1) % count;
+        }
+        if actions.just_pressed(Action::Confirm) {
+            if let Ok((button, _)) = buttons.get(children[list.focused.min(count - 1)]) {
+                selected.send(MenuSelected(button.action));
+            }
+        } else if actions.just_pressed(Action::Back) {
+            if let Some(back) = list.back {
+                selected.send(MenuSelected(back));
+            }
+        }
+    }
+}
 
-impl Default for Blink {
-    fn default() -> Self {
-        Self(Timer::from_seconds(0.5, TimerMode::Repeating))
+fn handle_menu_selection(
+    mut selected: EventReader<MenuSelected>,
+    pause_state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+    mut next_options: ResMut<NextState<OptionsState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for MenuSelected(action) in selected.read() {
+        match action {
+            MenuAction::Play => next_state.set(AppState::InGame),
+            MenuAction::Options => next_options.set(OptionsState::Open),
+            MenuAction::HighScores => next_state.set(AppState::HighScores),
+            MenuAction::Quit => exit.send(AppExit),
+            MenuAction::Resume => next_pause.set(PauseState::Running),
+            MenuAction::MainMenu => {
+                if *pause_state.get() == PauseState::Paused {
+                    next_pause.set(PauseState::Running);
+                }
+                next_state.set(AppState::Menu);
+            }
+        }
+    }
+}
+
+/// The focused button gets a highlighted border and label, hovering and pressing lighten the
+/// background
+fn style_menu_buttons(
+    lists: Query<(&MenuList, &Children)>,
+    mut buttons: Query<(
+        &MenuButton,
+        &Interaction,
+        &Children,
+        &mut BackgroundColor,
+        &mut BorderColor,
+    )>,
+    mut labels: Query<&mut Text>,
+) {
+    for (list, children) in lists.iter() {
+        let mut iter = buttons.iter_many_mut(children);
+        while let Some((button, interaction, button_children, mut background, mut border)) =
+            iter.fetch_next()
+        {
+            let focused = button.index == list.focused;
+            let (border_color, label_color) = if focused {
+                (Color::YELLOW, Color::YELLOW)
+            } else {
+                (Color::NONE, Color::WHITE)
+            };
+            let background_color = match interaction {
+                Interaction::Pressed => PRESSED_BUTTON_COLOR,
+                Interaction::Hovered => HOVERED_BUTTON_COLOR,
+                Interaction::None => BUTTON_COLOR,
+            };
+            // Only write on a change, so the labels aren't laid out again every frame
+            if background.0 != background_color {
+                background.0 = background_color;
+            }
+            if border.0 != border_color {
+                border.0 = border_color;
+            }
+            let mut label_iter = labels.iter_many_mut(button_children);
+            while let Some(mut text) = label_iter.fetch_next() {
+                if text
+                    .sections
+                    .iter()
+                    .any(|section| section.style.color != label_color)
+                {
+                    for section in text.sections.iter_mut() {
+                        section.style.color = label_color;
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -63,17 +297,17 @@ fn setup_menu(mut commands: Commands, font_assets: Res<Fonts>) {
                     color: Color::WHITE,
                 },
             ));
-            parent.spawn((
-                Blink::default(),
-                TextBundle::from_section(
-                    "PRESS ENTER TO PLAY",
-                    TextStyle {
-                        font: font_assets.press_start.clone(),
-                        font_size: 18.0,
-                        color: Color::YELLOW,
-                    },
-                ),
-            ));
+            spawn_menu_list(
+                parent,
+                font_assets.press_start.clone(),
+                &[
+                    ("PLAY", MenuAction::Play),
+                    ("OPTIONS", MenuAction::Options),
+                    ("HIGH SCORES", MenuAction::HighScores),
+                    ("QUIT", MenuAction::Quit),
+                ],
+                None,
+            );
         });
 }
 
@@ -83,74 +317,271 @@ fn cleanup_menu(mut commands: Commands, query: Query<Entity, With<Menu>>) {
     }
 }
 
-fn handle_menu_input(
-    state: Res<State<AppState>>,
-    mut next_state: ResMut<NextState<AppState>>,
-    input: Res<Input<KeyCode>>,
+fn setup_high_scores(
+    mut commands: Commands,
+    font_assets: Res<Fonts>,
+    high_scores: Res<HighScores>,
 ) {
-    for key in input.get_just_released() {
-        if *key == KeyCode::Return {
-            match state.get() {
-                AppState::Menu => next_state.set(AppState::InGame),
-                AppState::GameOver => next_state.set(AppState::Menu),
-                _ => (),
+    let text = |value: String, color: Color| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.press_start.clone(),
+                font_size: 10.0,
+                color,
+            },
+        )
+    };
+
+    commands
+        .spawn((
+            HighScoreScreen,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(40.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "HIGH SCORES",
+                TextStyle {
+                    font: font_assets.press_start.clone(),
+                    font_size: 30.0,
+                    color: Color::WHITE,
+                },
+            ));
+            if high_scores.entries().is_empty() {
+                parent.spawn(text("NO SCORES YET".into(), Color::GRAY));
+            } else {
+                parent.spawn(table_node(6)).with_children(|table| {
+                    for header in ["#", "NAME", "SCORE", "TIME", "KILLS", "DATE"] {
+                        table.spawn(text(header.into(), Color::YELLOW));
+                    }
+                    for (rank, entry) in high_scores.entries().iter().enumerate() {
+                        let seconds = entry.time as u32;
+                        table.spawn(text(format!("{}", rank + 1), Color::GRAY));
+                        table.spawn(text(entry.initials.clone(), Color::WHITE));
+                        table.spawn(text(entry.score.to_string(), Color::WHITE));
+                        table.spawn(text(
+                            format!("{:02}:{:02}", seconds / 60, seconds % 60),
+                            Color::WHITE,
+                        ));
+                        table.spawn(text(entry.kills.to_string(), Color::WHITE));
+                        table.spawn(text(entry.date_string(), Color::GRAY));
+                    }
+                });
             }
-        }
+            spawn_menu_list(
+                parent,
+                font_assets.press_start.clone(),
+                &[("BACK", MenuAction::MainMenu)],
+                Some(MenuAction::MainMenu),
+            );
+        });
+}
+
+fn cleanup_high_scores(mut commands: Commands, query: Query<Entity, With<HighScoreScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
-fn blink_text(mut query: Query<(&mut Visibility, &mut Blink)>, time: Res<Time>) {
-    for (mut visibility, mut blink) in query.iter_mut() {
-        if blink.0.tick(time.delta()).just_finished() {
-            *visibility = match *visibility {
-                Visibility::Inherited | Visibility::Visible => Visibility::Hidden,
-                Visibility::Hidden => Visibility::Visible,
-            };
-        }
+/// The pause action pauses and resumes, the rest of the pause menu is a [`MenuList`]
+fn handle_pause_input(
+    actions: Res<ActionState>,
+    pause_state: Res<State<PauseState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        next_pause.set(match pause_state.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
     }
 }
 
-fn setup_end_screen(mut commands: Commands, font_assets: Res<Fonts>) {
+fn setup_pause_menu(
+    mut commands: Commands,
+    font_assets: Res<Fonts>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    time.pause();
+    let style = |font_size: f32, color: Color| TextStyle {
+        font: font_assets.press_start.clone(),
+        font_size,
+        color,
+    };
     commands
         .spawn((
-            GameOver,
+            PauseMenu,
             NodeBundle {
                 style: Style {
-                    // center button
+                    position_type: PositionType::Absolute,
                     width: Val::Percent(100.),
                     height: Val::Percent(100.),
                     justify_content: JustifyContent::Center,
                     align_items: AlignItems::Center,
                     flex_direction: FlexDirection::Column,
-                    row_gap: Val::Px(100.0),
+                    row_gap: Val::Px(20.0),
                     ..default()
                 },
+                background_color: Color::rgba(0., 0., 0., 0.6).into(),
                 ..default()
             },
         ))
         .with_children(|parent| {
             parent.spawn(TextBundle::from_section(
-                "GAME OVER",
-                TextStyle {
-                    font: font_assets.press_start.clone(),
-                    font_size: 50.0,
-                    color: Color::MAROON,
-                },
-            ));
-            parent.spawn((
-                Blink::default(),
-                TextBundle::from_section(
-                    "PRESS ENTER TO CONTINUE",
-                    TextStyle {
-                        font: font_assets.press_start.clone(),
-                        font_size: 18.0,
-                        color: Color::YELLOW,
-                    },
-                ),
+                "PAUSED",
+                style(40.0, Color::WHITE),
             ));
+            spawn_menu_list(
+                parent,
+                font_assets.press_start.clone(),
+                &[
+                    ("RESUME", MenuAction::Resume),
+                    ("OPTIONS", MenuAction::Options),
+                    ("QUIT TO MENU", MenuAction::MainMenu),
+                ],
+                Some(MenuAction::Resume),
+            );
+        });
+}
+
+fn cleanup_pause_menu(
+    mut commands: Commands,
+    query: Query<Entity, With<PauseMenu>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    time.unpause();
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn setup_end_screen(mut commands: Commands, font_assets: Res<Fonts>, stats: Res<RunStats>) {
+    let text = |value: String, font_size: f32, color: Color| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font_assets.press_start.clone(),
+                font_size,
+                color,
+            },
+        )
+    };
+
+    let seconds = stats.time.elapsed_secs();
+    let summary = [
+        (
+            "TIME SURVIVED",
+            format!("{:02}:{:02}", seconds as u32 / 60, seconds as u32 % 60),
+        ),
+        ("LEVEL REACHED", stats.level.to_string()),
+        ("ENEMIES KILLED", stats.kills.to_string()),
+        ("DAMAGE TAKEN", stats.damage_taken.to_string()),
+        ("PICKUPS", stats.pickups.to_string()),
+    ];
+
+    let mut weapons: Vec<_> = stats.weapons.iter().collect();
+    weapons.sort_by(|(_, a), (_, b)| b.damage.cmp(&a.damage));
+
+    let mut kills: Vec<_> = stats.kills_by_type.iter().collect();
+    kills.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+
+    commands
+        .spawn((
+            GameOver,
+            NodeBundle {
+                style: Style {
+                    // center button
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(30.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(text("GAME OVER".into(), 50.0, Color::MAROON));
+
+            parent.spawn(table_node(2)).with_children(|table| {
+                for (label, value) in summary {
+                    table.spawn(text(label.into(), 12.0, Color::GRAY));
+                    table.spawn(text(value, 12.0, Color::WHITE));
+                }
+            });
+
+            // Weapon damage breakdown
+            parent.spawn(table_node(4)).with_children(|table| {
+                for header in ["WEAPON", "DAMAGE", "TIME", "DPS"] {
+                    table.spawn(text(header.into(), 10.0, Color::YELLOW));
+                }
+                for (weapon, weapon_stats) in weapons {
+                    let held = (seconds - weapon_stats.equipped_at).max(0.) as u32;
+                    table.spawn(text(weapon.name().to_uppercase(), 10.0, Color::WHITE));
+                    table.spawn(text(weapon_stats.damage.to_string(), 10.0, Color::WHITE));
+                    table.spawn(text(
+                        format!("{:02}:{:02}", held / 60, held % 60),
+                        10.0,
+                        Color::WHITE,
+                    ));
+                    table.spawn(text(
+                        format!("{:.1}", weapon_stats.dps(seconds)),
+                        10.0,
+                        Color::WHITE,
+                    ));
+                }
+            });
+
+            // Kills per enemy type
+            parent.spawn(table_node(2)).with_children(|table| {
+                for header in ["ENEMY", "KILLS"] {
+                    table.spawn(text(header.into(), 10.0, Color::YELLOW));
+                }
+                for (name, count) in kills {
+                    table.spawn(text(name.to_uppercase(), 10.0, Color::WHITE));
+                    table.spawn(text(count.to_string(), 10.0, Color::WHITE));
+                }
+            });
+
+            spawn_menu_list(
+                parent,
+                font_assets.press_start.clone(),
+                &[
+                    ("PLAY AGAIN", MenuAction::Play),
+                    ("MAIN MENU", MenuAction::MainMenu),
+                ],
+                None,
+            );
         });
 }
 
+/// A grid with `columns` columns sized to fit their contents, filled row by row by its children
+fn table_node(columns: u16) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            display: Display::Grid,
+            grid_template_columns: RepeatedGridTrack::auto(columns),
+            column_gap: Val::Px(24.0),
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        ..default()
+    }
+}
+
 fn cleanup_end_screen(mut commands: Commands, query: Query<Entity, With<GameOver>>) {
     if let Ok(menu) = query.get_single() {
         commands.entity(menu).despawn_recursive();
@@ -16,6 +16,16 @@ pub struct Velocity {
     pub value: Vec3,
 }
 
+/// Scales the distance covered by an entity's velocity each frame.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SpeedModifier(pub f32);
+
+impl Default for SpeedModifier {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
 #[derive(Component, Debug, Clone, Default)]
 pub struct Facing {
     pub value: Vec3,
@@ -38,6 +48,11 @@ impl Velocity {
     }
 }
 
+/// The rotation that turns a sprite drawn pointing up to point along `direction`
+pub fn rotation_from_direction(direction: Vec3) -> Quat {
+    Quat::from_rotation_z(direction.y.atan2(direction.x) - std::f32::consts::FRAC_PI_2)
+}
+
 #[derive(Bundle, Debug, Clone, Default)]
 pub struct MovementBundle {
     pub velocity: Velocity,
@@ -73,9 +88,13 @@ impl Plugin for MovementPlugin {
     }
 }
 
-fn update_position(mut query: Query<(&Velocity, &mut Transform)>, time: Res<Time>) {
-    for (velocity, mut transform) in query.iter_mut() {
-        transform.translation += velocity.value * time.delta_seconds();
+fn update_position(
+    mut query: Query<(&Velocity, &mut Transform, Option<&SpeedModifier>)>,
+    time: Res<Time>,
+) {
+    for (velocity, mut transform, modifier) in query.iter_mut() {
+        let modifier = modifier.copied().unwrap_or_default();
+        transform.translation += velocity.value * modifier.0 * time.delta_seconds();
     }
 }
 
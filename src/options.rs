@@ -0,0 +1,491 @@
+use bevy::prelude::*;
+
+use crate::{
+    asset_loader::Fonts,
+    camera::MAX_ZOOM,
+    input::{Action, ActionState, InputBindings, ACTIONS},
+    schedule::OptionsState,
+    settings::{Settings, RESOLUTIONS},
+};
+
+const VOLUME_STEP: f32 = 0.1;
+const SCREEN_SHAKE_STEP: f32 = 0.25;
+const SELECTED_COLOR: Color = Color::YELLOW;
+const UNSELECTED_COLOR: Color = Color::WHITE;
+const CONFLICT_COLOR: Color = Color::ORANGE_RED;
+/// Cancels waiting for a new binding. Not bound to anything by default, so everything that is
+/// can still be rebound.
+const CANCEL_REBIND_KEY: KeyCode = KeyCode::Delete;
+
+pub struct OptionsPlugin;
+
+impl Plugin for OptionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OptionsSelection>()
+            .init_resource::<ControlsSelection>()
+            .add_systems(
+                OnTransition {
+                    from: OptionsState::Closed,
+                    to: OptionsState::Open,
+                },
+                reset_selection,
+            )
+            .add_systems(OnEnter(OptionsState::Open), setup_options)
+            .add_systems(OnExit(OptionsState::Open), cleanup_options)
+            .add_systems(
+                Update,
+                (handle_options_input, update_option_rows)
+                    .chain()
+                    .run_if(in_state(OptionsState::Open)),
+            )
+            .add_systems(OnEnter(OptionsState::Controls), setup_controls)
+            .add_systems(OnExit(OptionsState::Controls), cleanup_controls)
+            .add_systems(
+                Update,
+                (handle_controls_input, update_control_rows)
+                    .chain()
+                    .run_if(in_state(OptionsState::Controls)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OptionItem {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    DisplayMode,
+    Resolution,
+    Zoom,
+    DamageNumbers,
+    ScreenShake,
+    HitStop,
+    ReduceFlashing,
+    MouseAim,
+    Controls,
+    Back,
+}
+
+const OPTION_ITEMS: [OptionItem; 13] = [
+    OptionItem::MasterVolume,
+    OptionItem::MusicVolume,
+    OptionItem::SfxVolume,
+    OptionItem::DisplayMode,
+    OptionItem::Resolution,
+    OptionItem::Zoom,
+    OptionItem::DamageNumbers,
+    OptionItem::ScreenShake,
+    OptionItem::HitStop,
+    OptionItem::ReduceFlashing,
+    OptionItem::MouseAim,
+    OptionItem::Controls,
+    OptionItem::Back,
+];
+
+impl OptionItem {
+    fn label(&self) -> &'static str {
+        match self {
+            OptionItem::MasterVolume => "MASTER VOLUME",
+            OptionItem::MusicVolume => "MUSIC VOLUME",
+            OptionItem::SfxVolume => "SFX VOLUME",
+            OptionItem::DisplayMode => "WINDOW MODE",
+            OptionItem::Resolution => "RESOLUTION",
+            OptionItem::Zoom => "ZOOM",
+            OptionItem::DamageNumbers => "DAMAGE NUMBERS",
+            OptionItem::ScreenShake => "SCREEN SHAKE",
+            OptionItem::HitStop => "HIT STOP",
+            OptionItem::ReduceFlashing => "REDUCE FLASHING",
+            OptionItem::MouseAim => "AIM WITH MOUSE",
+            OptionItem::Controls => "CONTROLS",
+            OptionItem::Back => "BACK",
+        }
+    }
+
+    fn value(&self, settings: &Settings) -> String {
+        let percent = |value: f32| format!("{}%", (value * 100.).round() as u32);
+        let on_off = |value: bool| if value { "ON" } else { "OFF" }.to_owned();
+        match self {
+            OptionItem::MasterVolume => percent(settings.master_volume),
+            // Kept for when there's audio to play, so the player isn't left wondering
+            OptionItem::MusicVolume => format!("{} (NO AUDIO YET)", percent(settings.music_volume)),
+            OptionItem::SfxVolume => format!("{} (NO AUDIO YET)", percent(settings.sfx_volume)),
+            OptionItem::DisplayMode => settings.display_mode.name().to_owned(),
+            OptionItem::Resolution => {
+                let (width, height) = settings.resolution;
+                format!("{width}X{height}")
+            }
+            OptionItem::Zoom => format!("{}X", settings.zoom),
+            OptionItem::DamageNumbers => on_off(settings.damage_numbers),
+            OptionItem::ScreenShake => percent(settings.screen_shake),
+            OptionItem::HitStop => on_off(settings.hit_stop),
+            OptionItem::ReduceFlashing => on_off(settings.reduce_flashing),
+            OptionItem::MouseAim => on_off(settings.mouse_aim),
+            OptionItem::Controls | OptionItem::Back => String::new(),
+        }
+    }
+
+    /// Changes the setting one step up (`step` > 0) or down (`step` < 0)
+    fn adjust(&self, settings: &mut Settings, step: i32) {
+        let step_volume = |volume: f32| (volume + VOLUME_STEP * step as f32).clamp(0., 1.);
+        match self {
+            OptionItem::MasterVolume => {
+                settings.master_volume = step_volume(settings.master_volume)
+            }
+            OptionItem::MusicVolume => settings.music_volume = step_volume(settings.music_volume),
+            OptionItem::SfxVolume => settings.sfx_volume = step_volume(settings.sfx_volume),
+            OptionItem::DisplayMode => {
+                settings.display_mode = if step > 0 {
+                    settings.display_mode.next()
+                } else {
+                    settings.display_mode.previous()
+                }
+            }
+            OptionItem::Resolution => {
+                let current = RESOLUTIONS
+                    .iter()
+                    .position(|resolution| *resolution == settings.resolution)
+                    .unwrap_or_default() as i32;
+                let next = (current + step).rem_euclid(RESOLUTIONS.len() as i32);
+                settings.resolution = RESOLUTIONS[next as usize];
+            }
+            OptionItem::Zoom => {
+                settings.zoom = (settings.zoom as i32 + step).clamp(1, MAX_ZOOM as i32) as u32
+            }
+            OptionItem::DamageNumbers => settings.damage_numbers = !settings.damage_numbers,
+            OptionItem::ScreenShake => {
+                settings.screen_shake =
+                    (settings.screen_shake + SCREEN_SHAKE_STEP * step as f32).clamp(0., 1.)
+            }
+            OptionItem::HitStop => settings.hit_stop = !settings.hit_stop,
+            OptionItem::ReduceFlashing => settings.reduce_flashing = !settings.reduce_flashing,
+            OptionItem::MouseAim => settings.mouse_aim = !settings.mouse_aim,
+            OptionItem::Controls | OptionItem::Back => (),
+        }
+    }
+}
+
+/// The index of the highlighted row in [`OPTION_ITEMS`].
+#[derive(Resource, Debug, Default)]
+struct OptionsSelection(usize);
+
+#[derive(Component, Debug)]
+struct OptionsMenu;
+
+#[derive(Component, Debug)]
+struct OptionRow(OptionItem);
+
+fn reset_selection(mut selection: ResMut<OptionsSelection>) {
+    selection.0 = 0;
+}
+
+/// A full screen panel drawn above whichever menu opened it
+fn overlay_node() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(12.0),
+            ..default()
+        },
+        background_color: Color::rgba(0.1, 0., 0.15, 0.95).into(),
+        z_index: ZIndex::Global(10),
+        ..default()
+    }
+}
+
+fn setup_options(mut commands: Commands, font_assets: Res<Fonts>) {
+    let style = |font_size: f32| TextStyle {
+        font: font_assets.press_start.clone(),
+        font_size,
+        color: UNSELECTED_COLOR,
+    };
+    commands
+        .spawn((OptionsMenu, overlay_node()))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("OPTIONS", style(30.0)));
+            for item in OPTION_ITEMS {
+                parent.spawn((
+                    OptionRow(item),
+                    TextBundle::from_sections([
+                        TextSection::new(item.label(), style(12.0)),
+                        TextSection::new("", style(12.0)),
+                    ]),
+                ));
+            }
+            parent.spawn(TextBundle::from_section(
+                "UP/DOWN SELECT  LEFT/RIGHT CHANGE  BACK TO LEAVE",
+                style(8.0),
+            ));
+        });
+}
+
+fn cleanup_options(mut commands: Commands, query: Query<Entity, With<OptionsMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_options_input(
+    actions: Res<ActionState>,
+    mut selection: ResMut<OptionsSelection>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<OptionsState>>,
+) {
+    let count = OPTION_ITEMS.len();
+    if actions.just_pressed(Action::Up) {
+        selection.0 = (selection.0 + count - 1) % count;
+    }
+    if actions.just_pressed(Action::Down) {
+        selection.0 = (selection.0 + 1) % count;
+    }
+
+    let item = OPTION_ITEMS[selection.0];
+    let step = if actions.just_pressed(Action::Left) {
+        -1
+    } else if actions.just_pressed(Action::Right) || actions.just_pressed(Action::Confirm) {
+        1
+    } else {
+        0
+    };
+
+    // Screens are left on release, so the menu underneath doesn't also see confirm come up
+    if actions.just_pressed(Action::Back)
+        || (item == OptionItem::Back && actions.just_released(Action::Confirm))
+    {
+        next_state.set(OptionsState::Closed);
+    } else if item == OptionItem::Controls && actions.just_released(Action::Confirm) {
+        next_state.set(OptionsState::Controls);
+    } else if step != 0 {
+        // Only touch the resource on a real change so it isn't saved needlessly
+        let mut changed = settings.clone();
+        item.adjust(&mut changed, step);
+        if changed != *settings {
+            *settings = changed;
+        }
+    }
+}
+
+fn update_option_rows(
+    mut rows: Query<(&OptionRow, &mut Text)>,
+    selection: Res<OptionsSelection>,
+    settings: Res<Settings>,
+) {
+    for (row, mut text) in rows.iter_mut() {
+        let selected = OPTION_ITEMS[selection.0] == row.0;
+        let color = if selected {
+            SELECTED_COLOR
+        } else {
+            UNSELECTED_COLOR
+        };
+        let value = row.0.value(&settings);
+        let value = if value.is_empty() {
+            value
+        } else {
+            format!("  < {value} >")
+        };
+        let [label, shown] = &mut text.sections[..] else {
+            continue;
+        };
+        label.style.color = color;
+        shown.style.color = color;
+        if shown.value != value {
+            shown.value = value;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlsItem {
+    Rebind(Action),
+    ResetDefaults,
+    Back,
+}
+
+impl ControlsItem {
+    fn all() -> impl Iterator<Item = ControlsItem> {
+        ACTIONS
+            .into_iter()
+            .map(ControlsItem::Rebind)
+            .chain([ControlsItem::ResetDefaults, ControlsItem::Back])
+    }
+
+    fn count() -> usize {
+        ACTIONS.len() + 2
+    }
+}
+
+/// The highlighted row on the controls screen, and the action waiting for a new binding.
+#[derive(Resource, Debug, Default)]
+struct ControlsSelection {
+    index: usize,
+    waiting: Option<Action>,
+    /// Set once a binding is chosen or cancelled. The key that did it would otherwise count as
+    /// a fresh press of whatever it's now bound to, so input is ignored until it's let go.
+    suppress_until_released: bool,
+}
+
+#[derive(Component, Debug)]
+struct ControlsMenu;
+
+#[derive(Component, Debug)]
+struct ControlsRow(ControlsItem);
+
+fn setup_controls(
+    mut commands: Commands,
+    mut selection: ResMut<ControlsSelection>,
+    font_assets: Res<Fonts>,
+) {
+    *selection = ControlsSelection::default();
+    let style = |font_size: f32| TextStyle {
+        font: font_assets.press_start.clone(),
+        font_size,
+        color: UNSELECTED_COLOR,
+    };
+    commands
+        .spawn((ControlsMenu, overlay_node()))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("CONTROLS", style(30.0)));
+            for item in ControlsItem::all() {
+                let label = match item {
+                    ControlsItem::Rebind(action) => action.name(),
+                    ControlsItem::ResetDefaults => "RESET DEFAULTS",
+                    ControlsItem::Back => "BACK",
+                };
+                parent.spawn((
+                    ControlsRow(item),
+                    TextBundle::from_sections([
+                        TextSection::new(label, style(12.0)),
+                        TextSection::new("", style(8.0)),
+                        TextSection::new(
+                            "",
+                            TextStyle {
+                                color: CONFLICT_COLOR,
+                                ..style(8.0)
+                            },
+                        ),
+                    ]),
+                ));
+            }
+            parent.spawn(TextBundle::from_section(
+                "CONFIRM ON AN ACTION, THEN PRESS THE NEW KEY OR BUTTON. DELETE CANCELS",
+                style(8.0),
+            ));
+        });
+}
+
+fn cleanup_controls(mut commands: Commands, query: Query<Entity, With<ControlsMenu>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_controls_input(
+    actions: Res<ActionState>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut selection: ResMut<ControlsSelection>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<OptionsState>>,
+) {
+    if selection.suppress_until_released {
+        if ACTIONS.iter().any(|action| actions.pressed(*action)) {
+            return;
+        }
+        selection.suppress_until_released = false;
+    }
+
+    // The next key or button pressed becomes the binding, whatever it is
+    if let Some(action) = selection.waiting {
+        if keys.just_pressed(CANCEL_REBIND_KEY) {
+            selection.waiting = None;
+        } else if let Some(key) = keys.get_just_pressed().next() {
+            settings.bindings.bind_key(action, *key);
+            selection.waiting = None;
+        } else if let Some(button) = buttons.get_just_pressed().next() {
+            settings.bindings.bind_button(action, button.button_type);
+            selection.waiting = None;
+        }
+        selection.suppress_until_released = selection.waiting.is_none();
+        return;
+    }
+
+    let count = ControlsItem::count();
+    if actions.just_pressed(Action::Up) {
+        selection.index = (selection.index + count - 1) % count;
+    }
+    if actions.just_pressed(Action::Down) {
+        selection.index = (selection.index + 1) % count;
+    }
+
+    if actions.just_pressed(Action::Back) {
+        next_state.set(OptionsState::Open);
+        return;
+    }
+    let Some(item) = ControlsItem::all().nth(selection.index) else {
+        return;
+    };
+    match item {
+        ControlsItem::Rebind(action) if actions.just_pressed(Action::Confirm) => {
+            selection.waiting = Some(action);
+        }
+        ControlsItem::ResetDefaults if actions.just_pressed(Action::Confirm) => {
+            if settings.bindings != InputBindings::default() {
+                settings.bindings = InputBindings::default();
+            }
+        }
+        ControlsItem::Back if actions.just_released(Action::Confirm) => {
+            next_state.set(OptionsState::Open);
+        }
+        _ => (),
+    }
+}
+
+fn update_control_rows(
+    mut rows: Query<(&ControlsRow, &mut Text)>,
+    selection: Res<ControlsSelection>,
+    settings: Res<Settings>,
+) {
+    let selected_item = ControlsItem::all().nth(selection.index);
+    for (row, mut text) in rows.iter_mut() {
+        let color = if selected_item == Some(row.0) {
+            SELECTED_COLOR
+        } else {
+            UNSELECTED_COLOR
+        };
+        let value = match row.0 {
+            ControlsItem::Rebind(action) if selection.waiting == Some(action) => {
+                "  PRESS A KEY OR BUTTON".to_owned()
+            }
+            ControlsItem::Rebind(action) => format!("  {}", settings.bindings.describe(action)),
+            _ => String::new(),
+        };
+        // Overlapping bindings are allowed, but they're easy to make by accident
+        let warning = match row.0 {
+            ControlsItem::Rebind(action) => {
+                let conflicts = settings.bindings.conflicts(action);
+                if conflicts.is_empty() {
+                    String::new()
+                } else {
+                    let names: Vec<_> = conflicts.iter().map(Action::name).collect();
+                    format!("  ALSO {}", names.join(", "))
+                }
+            }
+            _ => String::new(),
+        };
+        let [label, shown, conflict] = &mut text.sections[..] else {
+            continue;
+        };
+        label.style.color = color;
+        shown.style.color = color;
+        if shown.value != value {
+            shown.value = value;
+        }
+        if conflict.value != warning {
+            conflict.value = warning;
+        }
+    }
+}
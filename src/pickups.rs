@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    asset_loader::SpriteAssets,
+    collision::{Collider, CollisionEvent},
+    damage::{DamagePipeline, DeathEvent},
+    enemies::Enemy,
+    health::HealEvent,
+    player::{Player, PlayerUpgrade, WeaponType},
+    rng::RunRng,
+    schedule::InGame,
+    status::{ApplyStatus, StatusEffect},
+};
+
+const PICKUP_SIZE: Vec2 = Vec2::splat(10.);
+const MAX_HEALTH_DROP_CHANCE: f32 = 0.01;
+const HEAL_DROP_CHANCE: f32 = 0.04;
+const HEAL_OVER_TIME_DROP_CHANCE: f32 = 0.03;
+const ARMOR_DROP_CHANCE: f32 = 0.005;
+const SHIELD_DROP_CHANCE: f32 = 0.005;
+const WEAPON_DROP_CHANCE: f32 = 0.004;
+/// The weapons that can drop, each equally likely
+const WEAPON_DROPS: [WeaponType; 11] = [
+    WeaponType::FireWand,
+    WeaponType::FrostWand,
+    WeaponType::PoisonVial,
+    WeaponType::OrbitingBlades,
+    WeaponType::HolyAura,
+    WeaponType::Sword,
+    WeaponType::LightningRod,
+    WeaponType::ThrowingAxe,
+    WeaponType::ArcaneOrb,
+    WeaponType::FireBomb,
+    WeaponType::WarHammer,
+];
+
+pub struct PickupsPlugin;
+
+impl Plugin for PickupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<PickupBundle>("heal_pickup")
+            .add_event::<PickupCollected>()
+            .add_systems(
+                Update,
+                (collect_pickups, drop_pickups.after(DamagePipeline::Apply))
+                    .in_set(InGame::ProcessCombat),
+            );
+    }
+}
+
+/// Sent when the player picks something up.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct PickupCollected(pub Pickup);
+
+#[derive(Component, Debug, Clone, Copy)]
+pub enum Pickup {
+    /// Restores health immediately
+    Heal(u32),
+    /// Restores `amount` health spread over `duration` seconds
+    HealOverTime { amount: u32, duration: f32 },
+    /// Raises maximum health and heals the difference
+    MaxHealth(u32),
+    /// Permanently adds flat damage reduction
+    Armor(u32),
+    /// Permanently adds shield capacity
+    Shield(u32),
+    /// Gives the player a weapon or levels it up, see [`PlayerUpgrade::Weapon`]
+    Weapon(WeaponType),
+}
+
+impl Default for Pickup {
+    fn default() -> Self {
+        Self::Heal(0)
+    }
+}
+
+impl From<&EntityInstance> for Pickup {
+    fn from(value: &EntityInstance) -> Self {
+        let amount = value.get_int_field("heal").copied().unwrap_or_default() as u32;
+        match value.get_float_field("duration") {
+            Ok(duration) if *duration > 0. => Self::HealOverTime {
+                amount,
+                duration: *duration,
+            },
+            _ => Self::Heal(amount),
+        }
+    }
+}
+
+impl Pickup {
+    pub fn sprite_index(&self) -> usize {
+        match self {
+            Pickup::Heal(_) => 115,
+            Pickup::HealOverTime { .. } => 127,
+            Pickup::MaxHealth(_) => 113,
+            Pickup::Armor(_) => 101,
+            Pickup::Shield(_) => 102,
+            Pickup::Weapon(kind) => kind.sprite_index(),
+        }
+    }
+}
+
+#[derive(Bundle, LdtkEntity)]
+pub struct PickupBundle {
+    #[from_entity_instance]
+    pickup: Pickup,
+    #[sprite_sheet_bundle]
+    sprite: SpriteSheetBundle,
+    collider: Collider,
+}
+
+impl Default for PickupBundle {
+    fn default() -> Self {
+        Self {
+            pickup: Default::default(),
+            sprite: Default::default(),
+            collider: Collider::new(PICKUP_SIZE),
+        }
+    }
+}
+
+impl PickupBundle {
+    pub fn new(pickup: Pickup, texture_atlas: Handle<TextureAtlas>, position: Vec2) -> Self {
+        Self {
+            pickup,
+            sprite: SpriteSheetBundle {
+                sprite: TextureAtlasSprite::new(pickup.sprite_index()),
+                texture_atlas,
+                transform: Transform::from_translation(position.extend(50.)),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    mut events: EventReader<CollisionEvent>,
+    mut heal_events: EventWriter<HealEvent>,
+    mut upgrades: EventWriter<PlayerUpgrade>,
+    mut collected: EventWriter<PickupCollected>,
+    players: Query<(), With<Player>>,
+    pickups: Query<&Pickup>,
+) {
+    for collision in events.read() {
+        if !players.contains(collision.entity) {
+            continue;
+        }
+
+        let Ok(pickup) = pickups.get(collision.collided_with) else {
+            continue;
+        };
+
+        match *pickup {
+            Pickup::Heal(amount) => heal_events.send(HealEvent {
+                target: collision.entity,
+                amount,
+            }),
+            Pickup::HealOverTime { amount, duration } => commands.add(ApplyStatus {
+                entity: collision.entity,
+                effect: StatusEffect::heal(amount, duration),
+            }),
+            Pickup::MaxHealth(amount) => {
+                upgrades.send(PlayerUpgrade::MaxHealth { amount, heal: true })
+            }
+            Pickup::Armor(amount) => upgrades.send(PlayerUpgrade::Armor(amount)),
+            Pickup::Shield(amount) => upgrades.send(PlayerUpgrade::Shield(amount)),
+            Pickup::Weapon(kind) => upgrades.send(PlayerUpgrade::Weapon(kind)),
+        }
+        collected.send(PickupCollected(*pickup));
+        commands.entity(collision.collided_with).despawn_recursive();
+    }
+}
+
+fn drop_pickups(
+    mut commands: Commands,
+    mut deaths: EventReader<DeathEvent>,
+    mut rng: ResMut<RunRng>,
+    enemies: Query<(), With<Enemy>>,
+    sprite_assets: Res<SpriteAssets>,
+) {
+    for death in deaths.read() {
+        if !enemies.contains(death.entity) {
+            continue;
+        }
+
+        let weapon = rng
+            .chance(WEAPON_DROP_CHANCE)
+            .then(|| rng.choose(&WEAPON_DROPS).copied())
+            .flatten();
+        let pickup = if let Some(weapon) = weapon {
+            Pickup::Weapon(weapon)
+        } else if rng.chance(ARMOR_DROP_CHANCE) {
+            Pickup::Armor(1)
+        } else if rng.chance(SHIELD_DROP_CHANCE) {
+            Pickup::Shield(5)
+        } else if rng.chance(MAX_HEALTH_DROP_CHANCE) {
+            Pickup::MaxHealth(5)
+        } else if rng.chance(HEAL_DROP_CHANCE) {
+            Pickup::Heal(5)
+        } else if rng.chance(HEAL_OVER_TIME_DROP_CHANCE) {
+            Pickup::HealOverTime {
+                amount: 10,
+                duration: 5.,
+            }
+        } else {
+            continue;
+        };
+
+        commands.spawn(PickupBundle::new(
+            pickup,
+            sprite_assets.tiles.clone(),
+            death.position.truncate(),
+        ));
+    }
+}
@@ -1,28 +1,56 @@
 use crate::{
+    aim::AimSettings,
     asset_loader::SpriteAssets,
     collision::{Collider, CollisionDamage},
-    health::{Health, HealthBar},
-    movement::{Facing, MovementBundle, Velocity},
+    combat::{KnockBack, KnockBackForce},
+    damage::{Armor, CritStats, DamagePipeline, DamageRoll, DamageSource, DamageType, DeathEvent},
+    enemies::Enemy,
+    experience::Experience,
+    flash::BlinkWhileInvulnerable,
+    health::{HealEvent, Health, HealthBar, Regeneration, Shield},
+    input::{Action, ActionState},
+    movement::{rotation_from_direction, Facing, MovementBundle, Velocity},
+    projectiles::{Piercing, ProjectileMotion},
+    rng::RunRng,
     schedule::{AppState, InGame},
+    status::{InflictsStatus, StatusEffect},
 };
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
-use rand::random;
 
 const PLAYER_SPEED: f32 = 50.;
 const PLAYER_SIZE: Vec2 = Vec2::splat(16.);
 const PLAYER_STARTING_HEALTH: u32 = 30;
 const PLAYER_ATTACK_COOLDOWN: f32 = 1.0;
 const PLAYER_DAMAGE_COOLDOWN: f32 = 0.25;
+const PLAYER_CRIT_CHANCE: f32 = 0.05;
+const PLAYER_REGENERATION: f32 = 0.2;
+const PLAYER_DASH_DISTANCE: f32 = 40.;
+const PLAYER_DASH_DURATION: f32 = 0.15;
+const PLAYER_DASH_COOLDOWN: f32 = 1.0;
+/// Extra damage dealt by a thrown weapon for each level above 1
+const THROWN_LEVEL_DAMAGE_BONUS: f32 = 0.25;
+/// The only playable character so far, recorded with high scores
+pub const PLAYER_CHARACTER: &str = "Knight";
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.register_ldtk_entity::<PlayerBundle>("player")
+            .add_event::<PlayerUpgrade>()
             .add_systems(Update, throw_weapon.in_set(InGame::ProcessCombat))
-            .add_systems(Update, player_movement.in_set(InGame::UserInput))
-            .add_systems(Update, game_over.in_set(InGame::EntityUpdates))
+            .add_systems(Update, apply_upgrades.in_set(InGame::EntityUpdates))
+            .add_systems(
+                Update,
+                (player_movement, player_dash).in_set(InGame::UserInput),
+            )
+            .add_systems(
+                Update,
+                game_over
+                    .after(DamagePipeline::Apply)
+                    .in_set(InGame::ProcessCombat),
+            )
         // .add_systems(OnExit(AppState::InGame), reset_player)
         ;
     }
@@ -31,18 +59,138 @@ impl Plugin for PlayerPlugin {
 #[derive(Component, Debug, Default)]
 pub struct Player;
 
-#[derive(Debug, Default, Clone, Copy)]
+/// A permanent improvement to the player's defences, applied by [`apply_upgrades`].
+#[derive(Debug, Clone, Copy, Event)]
+pub enum PlayerUpgrade {
+    /// Raises maximum health, optionally healing by the same amount
+    MaxHealth { amount: u32, heal: bool },
+    /// Adds flat damage reduction
+    Armor(u32),
+    /// Adds shield capacity, giving the player a shield if they have none
+    Shield(u32),
+    /// Gives the player a weapon, or levels it up if they already have it. Weapons that aren't
+    /// thrown are handled by the plugin for that kind of weapon. The player only throws one
+    /// weapon at a time, so a different thrown weapon replaces theirs at level 1.
+    Weapon(WeaponType),
+}
+
+/// The highest level any weapon can reach
+pub const MAX_WEAPON_LEVEL: u32 = 5;
+
+/// A weapon the player is given by its first [`PlayerUpgrade::Weapon`], and that levels up with
+/// every one after that. Added to the player by [`add_or_level_up`].
+pub trait LeveledWeapon: Component {
+    const KIND: WeaponType;
+
+    /// The weapon at level 1
+    fn new() -> Self;
+
+    fn level_mut(&mut self) -> &mut u32;
+}
+
+/// Gives the player `W`, or levels it up if they already have it
+pub fn add_or_level_up<W: LeveledWeapon>(
+    mut commands: Commands,
+    mut upgrades: EventReader<PlayerUpgrade>,
+    mut players: Query<(Entity, Option<&mut W>), With<Player>>,
+) {
+    let Ok((player, mut weapon)) = players.get_single_mut() else {
+        return;
+    };
+
+    for upgrade in upgrades.read() {
+        if !matches!(upgrade, PlayerUpgrade::Weapon(kind) if *kind == W::KIND) {
+            continue;
+        }
+        match weapon.as_mut() {
+            Some(weapon) => {
+                let level = weapon.level_mut();
+                *level = (*level + 1).min(MAX_WEAPON_LEVEL);
+            }
+            None => {
+                commands.entity(player).insert(W::new());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum WeaponType {
     #[default]
     Dagger,
+    FireWand,
+    FrostWand,
+    PoisonVial,
+    OrbitingBlades,
+    HolyAura,
+    Sword,
+    LightningRod,
+    ThrowingAxe,
+    ArcaneOrb,
+    FireBomb,
+    WarHammer,
+}
+
+impl WeaponType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeaponType::Dagger => "Dagger",
+            WeaponType::FireWand => "Fire Wand",
+            WeaponType::FrostWand => "Frost Wand",
+            WeaponType::PoisonVial => "Poison Vial",
+            WeaponType::OrbitingBlades => "Orbiting Blades",
+            WeaponType::HolyAura => "Holy Aura",
+            WeaponType::Sword => "Sword",
+            WeaponType::LightningRod => "Lightning Rod",
+            WeaponType::ThrowingAxe => "Throwing Axe",
+            WeaponType::ArcaneOrb => "Arcane Orb",
+            WeaponType::FireBomb => "Fire Bomb",
+            WeaponType::WarHammer => "War Hammer",
+        }
+    }
+
+    pub fn sprite_index(&self) -> usize {
+        match self {
+            WeaponType::Dagger => 103,
+            WeaponType::FireWand => 129,
+            WeaponType::FrostWand => 130,
+            WeaponType::PoisonVial => 126,
+            WeaponType::OrbitingBlades => 104,
+            WeaponType::HolyAura => 116,
+            WeaponType::Sword => 106,
+            WeaponType::LightningRod => 131,
+            WeaponType::ThrowingAxe => 119,
+            WeaponType::ArcaneOrb => 128,
+            WeaponType::FireBomb => 114,
+            WeaponType::WarHammer => 117,
+        }
+    }
 }
 
+/// Where a weapon's projectiles fly. The targeting behaviors fire along [`Facing`] when there
+/// are no enemies on screen.
 #[derive(Debug, Default, Clone, Copy)]
 pub enum WeaponSpawnBehavior {
     #[default]
     FourDirections,
     Facing,
     Random,
+    /// At the closest enemy on screen
+    Nearest,
+    /// At the enemy on screen with the most health
+    Strongest,
+    /// At a random enemy on screen
+    RandomTarget,
+    /// `count` projectiles fanned evenly across `angle` radians, centered on [`Facing`]
+    Spread {
+        count: u32,
+        angle: f32,
+    },
+    /// `count` evenly spaced projectiles, turned `step` radians further with each volley
+    Spiral {
+        count: u32,
+        step: f32,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -50,45 +198,228 @@ pub struct WeaponSpec {
     speed: f32,
     distance: f32,
     damage: u32,
+    damage_type: DamageType,
+    crit: CritStats,
+    variance: f32,
     health: u32,
+    /// How many different enemies each projectile can hit before it breaks. Piercing
+    /// projectiles hit each enemy once and aren't worn down by touching them.
+    pierce: Option<u32>,
     behavior: WeaponSpawnBehavior,
     collider: Collider,
+    knockback: f32,
+    status: Option<StatusEffect>,
+    motion: ProjectileMotion,
 }
 
-impl From<WeaponType> for WeaponSpec {
-    fn from(value: WeaponType) -> Self {
-        match value {
+impl WeaponSpec {
+    /// The projectiles a weapon throws, `None` for weapons that stay attached to the player
+    fn projectile(kind: WeaponType) -> Option<Self> {
+        let spec = match kind {
             WeaponType::Dagger => Self {
                 speed: 100.0,
                 distance: 16.0,
                 damage: 5,
+                damage_type: DamageType::Physical,
+                crit: CritStats::new(0.1, 2.0),
+                variance: 0.2,
                 health: 1,
+                pierce: None,
                 collider: Collider::new(Vec2::new(8., 13.)),
                 behavior: WeaponSpawnBehavior::FourDirections,
+                knockback: 8.0,
+                status: None,
+                motion: ProjectileMotion::Straight,
             },
+            WeaponType::FireWand => Self {
+                speed: 80.0,
+                distance: 16.0,
+                damage: 2,
+                damage_type: DamageType::Fire,
+                crit: CritStats::new(0.05, 1.5),
+                variance: 0.5,
+                health: 1,
+                pierce: None,
+                collider: Collider::new(Vec2::new(6., 14.)),
+                behavior: WeaponSpawnBehavior::Spiral {
+                    count: 3,
+                    step: 0.4,
+                },
+                knockback: 4.0,
+                status: Some(StatusEffect::burn(2.0, 3.0)),
+                motion: ProjectileMotion::Straight,
+            },
+            WeaponType::FrostWand => Self {
+                speed: 80.0,
+                distance: 16.0,
+                damage: 1,
+                damage_type: DamageType::Ice,
+                crit: CritStats::new(0.05, 1.5),
+                variance: 0.0,
+                health: 1,
+                pierce: None,
+                collider: Collider::new(Vec2::new(8., 14.)),
+                behavior: WeaponSpawnBehavior::Random,
+                knockback: 0.0,
+                status: Some(StatusEffect::freeze(1.5)),
+                motion: ProjectileMotion::Straight,
+            },
+            WeaponType::PoisonVial => Self {
+                speed: 60.0,
+                distance: 8.0,
+                damage: 1,
+                damage_type: DamageType::Poison,
+                crit: CritStats::default(),
+                variance: 0.0,
+                health: 1,
+                pierce: None,
+                collider: Collider::new(Vec2::new(6., 10.)),
+                behavior: WeaponSpawnBehavior::RandomTarget,
+                knockback: 0.0,
+                status: Some(StatusEffect::poison(1.0, 5.0)),
+                motion: ProjectileMotion::Straight,
+            },
+            WeaponType::ThrowingAxe => Self {
+                speed: 120.0,
+                distance: 12.0,
+                damage: 4,
+                damage_type: DamageType::Physical,
+                crit: CritStats::new(0.1, 2.0),
+                variance: 0.2,
+                health: 1,
+                // Cuts through several enemies on the way out and back
+                pierce: Some(6),
+                collider: Collider::new(Vec2::new(12., 12.)),
+                behavior: WeaponSpawnBehavior::Facing,
+                knockback: 6.0,
+                status: None,
+                motion: ProjectileMotion::Boomerang {
+                    deceleration: 150.0,
+                },
+            },
+            WeaponType::ArcaneOrb => Self {
+                speed: 70.0,
+                distance: 12.0,
+                damage: 4,
+                damage_type: DamageType::Physical,
+                crit: CritStats::new(0.05, 1.5),
+                variance: 0.2,
+                health: 1,
+                pierce: None,
+                collider: Collider::new(Vec2::new(8., 10.)),
+                behavior: WeaponSpawnBehavior::Spread {
+                    count: 2,
+                    angle: std::f32::consts::FRAC_PI_2,
+                },
+                knockback: 2.0,
+                status: Some(StatusEffect::slow(0.4, 2.0)),
+                motion: ProjectileMotion::Homing { turn_rate: 3.0 },
+            },
+            WeaponType::FireBomb => Self {
+                speed: 60.0,
+                distance: 8.0,
+                damage: 1,
+                damage_type: DamageType::Fire,
+                crit: CritStats::default(),
+                variance: 0.1,
+                health: 1,
+                pierce: None,
+                collider: Collider::new(Vec2::new(8., 10.)),
+                behavior: WeaponSpawnBehavior::Nearest,
+                knockback: 0.0,
+                status: None,
+                motion: ProjectileMotion::Bomb {
+                    fuse: 1.5,
+                    radius: 32.0,
+                    damage: 8,
+                },
+            },
+            WeaponType::WarHammer => Self {
+                speed: 70.0,
+                distance: 12.0,
+                damage: 10,
+                damage_type: DamageType::Physical,
+                crit: CritStats::new(0.05, 2.0),
+                variance: 0.2,
+                health: 2,
+                pierce: None,
+                collider: Collider::new(Vec2::new(10., 12.)),
+                behavior: WeaponSpawnBehavior::Strongest,
+                knockback: 16.0,
+                status: None,
+                motion: ProjectileMotion::Straight,
+            },
+            WeaponType::OrbitingBlades
+            | WeaponType::HolyAura
+            | WeaponType::Sword
+            | WeaponType::LightningRod => return None,
+        };
+        Some(spec)
+    }
+
+    /// The spec with damage raised for a thrown weapon at `level`
+    fn at_level(mut self, level: u32) -> Self {
+        let bonus = 1.0 + THROWN_LEVEL_DAMAGE_BONUS * level.saturating_sub(1) as f32;
+        let scale = |damage: u32| (damage as f32 * bonus).round() as u32;
+        self.damage = scale(self.damage);
+        if let ProjectileMotion::Bomb { damage, .. } = &mut self.motion {
+            *damage = scale(*damage);
         }
+        self
     }
 }
 
 #[derive(Component, Debug)]
 pub struct Weapon {
     kind: WeaponType,
+    level: u32,
     cooldown: Timer,
+    /// Where the next [`WeaponSpawnBehavior::Spiral`] volley starts, in radians
+    spiral_angle: f32,
+}
+
+impl Weapon {
+    pub fn kind(&self) -> WeaponType {
+        self.kind
+    }
+
+    pub fn cooldown(&self) -> &Timer {
+        &self.cooldown
+    }
 }
 
 #[derive(Component, Debug)]
 pub struct Projectile;
 
+/// Time until the player can dash again.
+#[derive(Component, Debug)]
+pub struct Dash(Timer);
+
+impl Default for Dash {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(PLAYER_DASH_COOLDOWN, TimerMode::Once);
+        // Ready to use straight away
+        cooldown.tick(cooldown.duration());
+        Self(cooldown)
+    }
+}
+
 #[derive(Bundle, LdtkEntity)]
 pub struct PlayerBundle {
     player: Player,
     #[sprite_sheet_bundle]
     sprite_sheet_bundle: SpriteSheetBundle,
     health: Health,
+    regeneration: Regeneration,
     collider: Collider,
     weapon: Weapon,
+    crit: CritStats,
+    armor: Armor,
+    experience: Experience,
+    dash: Dash,
     movement: MovementBundle,
     health_bar: HealthBar,
+    blink: BlinkWhileInvulnerable,
 }
 
 impl Default for PlayerBundle {
@@ -97,52 +428,75 @@ impl Default for PlayerBundle {
             player: Player,
             sprite_sheet_bundle: Default::default(),
             health: Health::with_damage_cooldown(PLAYER_STARTING_HEALTH, PLAYER_DAMAGE_COOLDOWN),
+            regeneration: Regeneration::new(PLAYER_REGENERATION),
             collider: Collider::new(PLAYER_SIZE),
             weapon: Weapon {
                 kind: Default::default(),
+                level: 1,
                 cooldown: Timer::from_seconds(PLAYER_ATTACK_COOLDOWN, TimerMode::Repeating),
+                spiral_angle: 0.,
             },
+            crit: CritStats::new(PLAYER_CRIT_CHANCE, 1.0),
+            armor: Armor(0),
+            experience: Experience::default(),
+            dash: Dash::default(),
             movement: MovementBundle {
                 facing: Facing { value: Vec3::X },
                 ..Default::default()
             },
             health_bar: HealthBar,
+            blink: BlinkWhileInvulnerable,
         }
     }
 }
 
 fn player_movement(
     mut query: Query<(&mut Velocity, &mut Transform, &mut Facing), With<Player>>,
-    input: Res<Input<KeyCode>>,
+    actions: Res<ActionState>,
+    aim: Res<AimSettings>,
 ) {
-    let mut direction = Vec3::ZERO;
-    for key in input.get_pressed() {
-        match key {
-            KeyCode::W | KeyCode::Up => {
-                direction += Vec3::Y;
-            }
-            KeyCode::A | KeyCode::Left => {
-                direction += Vec3::NEG_X;
-            }
-            KeyCode::S | KeyCode::Down => {
-                direction += Vec3::NEG_Y;
-            }
-            KeyCode::D | KeyCode::Right => {
-                direction += Vec3::X;
-            }
-            _ => {}
-        }
-    }
+    // Analog sticks can ask for less than full speed
+    let direction = actions.movement().extend(0.);
     // NOTE: If the player has died/been despawned from losing all its health, this will panic.
     // We need to solve this by changing game states or guarding this access.
     let Ok((mut velocity, mut transform, mut facing)) = query.get_single_mut() else {
         return;
     };
-    if direction != Vec3::ZERO {
-        facing.value = direction;
+    // While aiming, facing follows the cursor or right stick instead
+    if direction != Vec3::ZERO && !aim.enabled {
+        facing.value = direction.normalize();
     }
     transform.translation.z = 100.0;
-    velocity.change_direction_speed(direction, PLAYER_SPEED);
+    velocity.value = direction * PLAYER_SPEED;
+}
+
+fn player_dash(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Dash, &Facing, Has<KnockBack>), With<Player>>,
+    actions: Res<ActionState>,
+    time: Res<Time>,
+) {
+    let Ok((player, mut dash, facing, knocked_back)) = query.get_single_mut() else {
+        return;
+    };
+    dash.0.tick(time.delta());
+    if !actions.just_pressed(Action::Dash) || !dash.0.finished() || knocked_back {
+        return;
+    }
+
+    // Dashes go where the player is moving, which isn't where they face while aiming
+    let movement = actions.movement().extend(0.);
+    let direction = if movement != Vec3::ZERO {
+        movement
+    } else {
+        facing.value
+    };
+    // A dash is a self inflicted knockback, so it stops at walls the same way
+    commands.entity(player).insert(KnockBack {
+        displacement: direction.normalize_or_zero() * PLAYER_DASH_DISTANCE,
+        duration: Timer::from_seconds(PLAYER_DASH_DURATION, TimerMode::Once),
+    });
+    dash.0.reset();
 }
 
 #[derive(Bundle)]
@@ -152,81 +506,111 @@ pub struct WeaponBundle {
     collider: Collider,
     health: Health,
     collision_damage: CollisionDamage,
+    knockback: KnockBackForce,
+    source: DamageSource,
+    roll: DamageRoll,
     movement: MovementBundle,
 }
 
 fn throw_weapon(
-    mut query: Query<(&mut Weapon, &Transform, &Facing), With<Player>>,
+    mut query: Query<(Entity, &mut Weapon, &CritStats, &Transform, &Facing), With<Player>>,
+    enemies: Query<(&Transform, &Health), With<Enemy>>,
+    camera: Query<(&GlobalTransform, &OrthographicProjection), With<Camera>>,
     time: Res<Time>,
+    mut rng: ResMut<RunRng>,
     mut commands: Commands,
     sprite_assets: Res<SpriteAssets>,
 ) {
-    let Ok((mut weapon, player_transform, facing)) = query.get_single_mut() else {
+    let Ok((player, mut weapon, crit, player_transform, facing)) = query.get_single_mut() else {
         return;
     };
     weapon.cooldown.tick(time.delta());
     if weapon.cooldown.just_finished() {
-        let spec: WeaponSpec = weapon.kind.into();
-
-        let spawns: Vec<(Transform, Velocity)> = match &spec.behavior {
-            WeaponSpawnBehavior::FourDirections => [Vec3::Y, Vec3::NEG_X, Vec3::NEG_Y, Vec3::X]
-                .into_iter()
-                .enumerate()
-                .map(|(i, direction)| {
-                    let mut transform = *player_transform;
-                    transform.translation += direction * spec.distance;
-                    let rotation =
-                        Quat::from_axis_angle(Vec3::Z, (i as f32) * std::f32::consts::FRAC_PI_2);
-                    transform.rotate(rotation);
-                    let velocity = Velocity::from_direction_speed(direction, spec.speed);
-                    (transform, velocity)
-                })
-                .collect(),
-            WeaponSpawnBehavior::Facing => {
-                let mut transform = *player_transform;
-                let direction = facing.value;
-                transform.translation += direction * spec.distance;
-                let rotation = Quat::from_axis_angle(
-                    Vec3::Z,
-                    if direction.y.signum() < 0. {
-                        Vec3::NEG_X.angle_between(direction) + std::f32::consts::FRAC_PI_2
-                    } else {
-                        Vec3::X.angle_between(direction) - std::f32::consts::FRAC_PI_2
-                    }, // TODO: What is the correct mathematical way to deal with this
-                );
-                transform.rotate(rotation);
-                vec![(
-                    transform,
-                    Velocity::from_direction_speed(direction, spec.speed),
-                )]
+        let Some(spec) =
+            WeaponSpec::projectile(weapon.kind).map(|spec| spec.at_level(weapon.level))
+        else {
+            return;
+        };
+        let origin = player_transform.translation;
+
+        // Targeting only considers enemies the player can see
+        let view = camera.get_single().ok().map(|(transform, projection)| {
+            let center = transform.translation().truncate();
+            Rect::from_corners(projection.area.min + center, projection.area.max + center)
+        });
+        let mut targets = enemies.iter().filter(|(transform, _)| {
+            view.map_or(true, |view| view.contains(transform.translation.truncate()))
+        });
+        let target = match spec.behavior {
+            WeaponSpawnBehavior::Nearest => targets.min_by(|(a, _), (b, _)| {
+                a.translation
+                    .distance_squared(origin)
+                    .total_cmp(&b.translation.distance_squared(origin))
+            }),
+            WeaponSpawnBehavior::Strongest => targets.max_by_key(|(_, health)| health.amount),
+            WeaponSpawnBehavior::RandomTarget => {
+                let targets: Vec<_> = targets.collect();
+                rng.choose(&targets).copied()
             }
+            _ => None,
+        };
+
+        let directions: Vec<Vec3> = match spec.behavior {
+            WeaponSpawnBehavior::FourDirections => vec![Vec3::Y, Vec3::NEG_X, Vec3::NEG_Y, Vec3::X],
+            WeaponSpawnBehavior::Facing => vec![facing.value],
             WeaponSpawnBehavior::Random => {
-                let mut transform = *player_transform;
-                let angle = random::<f32>() * std::f32::consts::TAU;
-                let direction = Vec2::from_angle(angle).extend(0.);
-                let rotation = Quat::from_axis_angle(
-                    Vec3::Z,
-                    if direction.y.signum() < 0. {
-                        Vec3::NEG_X.angle_between(direction) + std::f32::consts::FRAC_PI_2
-                    } else {
-                        Vec3::X.angle_between(direction) - std::f32::consts::FRAC_PI_2
-                    }, // TODO: What is the correct mathematical way to deal with this
-                );
-                transform.translation += direction * spec.distance;
-                transform.rotate(rotation);
-                vec![(
-                    transform,
-                    Velocity::from_direction_speed(direction, spec.speed),
-                )]
+                vec![Vec2::from_angle(rng.angle()).extend(0.)]
+            }
+            WeaponSpawnBehavior::Nearest
+            | WeaponSpawnBehavior::Strongest
+            | WeaponSpawnBehavior::RandomTarget => {
+                let direction = target
+                    .map(|(transform, _)| {
+                        (transform.translation - origin)
+                            .truncate()
+                            .normalize_or_zero()
+                            .extend(0.)
+                    })
+                    .filter(|direction| *direction != Vec3::ZERO);
+                vec![direction.unwrap_or(facing.value)]
+            }
+            WeaponSpawnBehavior::Spread { count, angle } => {
+                let center = facing.value.y.atan2(facing.value.x);
+                (0..count)
+                    .map(|i| {
+                        let offset = if count > 1 {
+                            angle * (i as f32 / (count - 1) as f32 - 0.5)
+                        } else {
+                            0.
+                        };
+                        Vec2::from_angle(center + offset).extend(0.)
+                    })
+                    .collect()
+            }
+            WeaponSpawnBehavior::Spiral { count, step } => {
+                let start = weapon.spiral_angle;
+                weapon.spiral_angle = (start + step) % std::f32::consts::TAU;
+                (0..count)
+                    .map(|i| {
+                        let angle = start + std::f32::consts::TAU * i as f32 / count as f32;
+                        Vec2::from_angle(angle).extend(0.)
+                    })
+                    .collect()
             }
         };
 
-        let sprite = match weapon.kind {
-            WeaponType::Dagger => TextureAtlasSprite::new(103),
-        };
+        let spawns = directions.into_iter().map(|direction| {
+            let mut transform = *player_transform;
+            transform.translation += direction * spec.distance;
+            transform.rotate(rotation_from_direction(direction));
+            let velocity = Velocity::from_direction_speed(direction, spec.speed);
+            (transform, velocity)
+        });
+
+        let sprite = TextureAtlasSprite::new(weapon.kind.sprite_index());
 
         for (transform, velocity) in spawns {
-            commands.spawn(WeaponBundle {
+            let mut projectile = commands.spawn(WeaponBundle {
                 spritesheet: SpriteSheetBundle {
                     texture_atlas: sprite_assets.tiles.clone(),
                     sprite: sprite.clone(),
@@ -240,14 +624,94 @@ fn throw_weapon(
                 projectile: Projectile,
                 collider: spec.collider.clone(),
                 health: Health::new(spec.health),
-                collision_damage: CollisionDamage::new(spec.damage),
+                collision_damage: CollisionDamage::with_type(spec.damage, spec.damage_type),
+                knockback: KnockBackForce(spec.knockback),
+                source: DamageSource {
+                    attacker: Some(player),
+                    weapon: Some(weapon.kind),
+                },
+                roll: DamageRoll {
+                    crit: crit.combine(&spec.crit),
+                    variance: spec.variance,
+                },
             });
+            if let Some(status) = &spec.status {
+                projectile.insert(InflictsStatus(status.clone()));
+            }
+            spec.motion.apply(&mut projectile, player, spec.speed);
+            if let Some(hits) = spec.pierce {
+                // Piercing projectiles deal their own damage, once per enemy
+                projectile
+                    .insert(Piercing::new(
+                        hits,
+                        CollisionDamage::with_type(spec.damage, spec.damage_type),
+                    ))
+                    .remove::<(Health, CollisionDamage)>();
+            }
         }
     }
 }
 
-fn game_over(mut next_state: ResMut<NextState<AppState>>, removed: RemovedComponents<Player>) {
-    if !removed.is_empty() {
+fn game_over(
+    mut next_state: ResMut<NextState<AppState>>,
+    mut deaths: EventReader<DeathEvent>,
+    players: Query<(), With<Player>>,
+) {
+    if deaths.read().any(|death| players.contains(death.entity)) {
         next_state.set(AppState::GameOver);
     }
 }
+
+fn apply_upgrades(
+    mut commands: Commands,
+    mut upgrades: EventReader<PlayerUpgrade>,
+    mut heal_events: EventWriter<HealEvent>,
+    mut players: Query<
+        (
+            Entity,
+            &mut Health,
+            &mut Armor,
+            Option<&mut Shield>,
+            &mut Weapon,
+        ),
+        With<Player>,
+    >,
+) {
+    let Ok((entity, mut health, mut armor, mut shield, mut weapon)) = players.get_single_mut()
+    else {
+        return;
+    };
+
+    for upgrade in upgrades.read() {
+        match *upgrade {
+            PlayerUpgrade::MaxHealth { amount, heal } => {
+                health.total += amount;
+                if heal {
+                    heal_events.send(HealEvent {
+                        target: entity,
+                        amount,
+                    });
+                }
+            }
+            PlayerUpgrade::Armor(amount) => armor.0 += amount,
+            PlayerUpgrade::Shield(amount) => match shield.as_mut() {
+                Some(shield) => {
+                    shield.total += amount;
+                    shield.amount += amount;
+                }
+                None => {
+                    commands.entity(entity).insert(Shield::new(amount));
+                }
+            },
+            PlayerUpgrade::Weapon(kind) if WeaponSpec::projectile(kind).is_none() => (),
+            PlayerUpgrade::Weapon(kind) if kind == weapon.kind => {
+                weapon.level = (weapon.level + 1).min(MAX_WEAPON_LEVEL);
+            }
+            PlayerUpgrade::Weapon(kind) => {
+                weapon.kind = kind;
+                weapon.level = 1;
+                weapon.spiral_angle = 0.;
+            }
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use bevy::{ecs::system::EntityCommands, prelude::*, sprite::MaterialMesh2dBundle, utils::HashSet};
+
+use crate::{
+    collision::{Collider, CollisionDamage},
+    damage::{DamagePipeline, DamageRequest, DamageRoll, DamageSource},
+    enemies::Enemy,
+    health::Health,
+    levels::Wall,
+    movement::{rotation_from_direction, Velocity},
+    schedule::InGame,
+};
+
+/// Boomerangs are caught once they're this close to their thrower
+const BOOMERANG_CATCH_DISTANCE: f32 = 8.;
+/// Radians per second
+const BOOMERANG_SPIN: f32 = 12.;
+/// Fraction of a bomb's damage still dealt at the edge of the blast
+const BOMB_EDGE_DAMAGE: f32 = 0.25;
+const EXPLOSION_DURATION: f32 = 0.3;
+const EXPLOSION_COLOR: Color = Color::rgba(1., 0.6, 0.2, 0.6);
+
+pub struct ProjectilesPlugin;
+
+impl Plugin for ProjectilesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (move_boomerangs, steer_homing, fade_explosions).in_set(InGame::EntityUpdates),
+        )
+        .add_systems(
+            Update,
+            (detonate_bombs, pierce_enemies).in_set(DamagePipeline::Request),
+        );
+    }
+}
+
+/// How a thrown projectile moves after leaving the player, on top of its [`Velocity`].
+#[derive(Debug, Default, Clone, Copy)]
+pub enum ProjectileMotion {
+    /// Keeps flying in a straight line
+    #[default]
+    Straight,
+    /// Slows down by `deceleration` per second, then speeds back up towards the thrower
+    Boomerang { deceleration: f32 },
+    /// Turns towards the nearest enemy by up to `turn_rate` radians per second
+    Homing { turn_rate: f32 },
+    /// Explodes on hitting an enemy or wall, or after `fuse` seconds, damaging every enemy
+    /// within `radius`. Enemies further from the middle take less damage.
+    Bomb { fuse: f32, radius: f32, damage: u32 },
+}
+
+impl ProjectileMotion {
+    /// Adds the components for this motion to a projectile thrown by `thrower`
+    pub fn apply(&self, projectile: &mut EntityCommands, thrower: Entity, speed: f32) {
+        match *self {
+            ProjectileMotion::Straight => (),
+            ProjectileMotion::Boomerang { deceleration } => {
+                projectile.insert(Boomerang {
+                    thrower,
+                    deceleration,
+                    max_speed: speed,
+                    returning: false,
+                });
+            }
+            ProjectileMotion::Homing { turn_rate } => {
+                projectile.insert(Homing {
+                    turn_rate,
+                    target: None,
+                });
+            }
+            ProjectileMotion::Bomb {
+                fuse,
+                radius,
+                damage,
+            } => {
+                // Bombs only go away by exploding, not by losing health when they hit something
+                projectile
+                    .insert(Bomb {
+                        fuse: Timer::from_seconds(fuse, TimerMode::Once),
+                        radius,
+                        damage,
+                    })
+                    .remove::<Health>();
+            }
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Boomerang {
+    pub thrower: Entity,
+    pub deceleration: f32,
+    max_speed: f32,
+    returning: bool,
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Homing {
+    /// Radians per second
+    pub turn_rate: f32,
+    target: Option<Entity>,
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Bomb {
+    pub fuse: Timer,
+    pub radius: f32,
+    pub damage: u32,
+}
+
+/// Lets a projectile pass through enemies, hitting each one once. It breaks after hitting
+/// `hits_left` different enemies.
+#[derive(Component, Debug, Clone)]
+pub struct Piercing {
+    pub hits_left: u32,
+    pub damage: CollisionDamage,
+    /// Enemies this projectile already hit
+    hit: HashSet<Entity>,
+}
+
+impl Piercing {
+    pub fn new(hits: u32, damage: CollisionDamage) -> Self {
+        Self {
+            hits_left: hits,
+            damage,
+            hit: HashSet::new(),
+        }
+    }
+}
+
+/// The blast left behind by a [`Bomb`], fading out over [`EXPLOSION_DURATION`].
+#[derive(Component, Debug)]
+struct Explosion(Timer);
+
+fn move_boomerangs(
+    mut commands: Commands,
+    mut boomerangs: Query<(Entity, &mut Boomerang, &mut Velocity, &mut Transform)>,
+    throwers: Query<&Transform, Without<Boomerang>>,
+    time: Res<Time>,
+) {
+    for (entity, mut boomerang, mut velocity, mut transform) in boomerangs.iter_mut() {
+        transform.rotate_z(BOOMERANG_SPIN * time.delta_seconds());
+        let change = boomerang.deceleration * time.delta_seconds();
+
+        if !boomerang.returning {
+            let speed = velocity.value.length() - change;
+            if speed > 0. {
+                velocity.change_direction_speed(velocity.value, speed);
+                continue;
+            }
+            boomerang.returning = true;
+        }
+
+        // A boomerang whose thrower is gone has nowhere to return to
+        let Ok(thrower) = throwers.get(boomerang.thrower) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        let to_thrower = thrower.translation - transform.translation;
+        if to_thrower.truncate().length() < BOOMERANG_CATCH_DISTANCE {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let speed = (velocity.value.length() + change).min(boomerang.max_speed);
+        velocity.change_direction_speed(to_thrower.truncate().extend(0.), speed);
+    }
+}
+
+fn steer_homing(
+    mut missiles: Query<(&mut Homing, &mut Velocity, &mut Transform)>,
+    enemies: Query<(Entity, &Transform), (With<Enemy>, Without<Homing>)>,
+    time: Res<Time>,
+) {
+    for (mut homing, mut velocity, mut transform) in missiles.iter_mut() {
+        let position = transform.translation;
+        // Pick the nearest enemy whenever the last target is gone
+        let target = match homing.target.and_then(|target| enemies.get(target).ok()) {
+            Some((_, target)) => Some(target.translation),
+            None => {
+                let nearest = enemies.iter().min_by(|(_, a), (_, b)| {
+                    a.translation
+                        .distance_squared(position)
+                        .total_cmp(&b.translation.distance_squared(position))
+                });
+                homing.target = nearest.map(|(entity, _)| entity);
+                nearest.map(|(_, target)| target.translation)
+            }
+        };
+        let Some(target) = target else {
+            continue;
+        };
+
+        let heading = velocity.value.truncate();
+        let wanted = (target - position).truncate();
+        if heading == Vec2::ZERO || wanted == Vec2::ZERO {
+            continue;
+        }
+        let max_turn = homing.turn_rate * time.delta_seconds();
+        let turn = heading.angle_between(wanted).clamp(-max_turn, max_turn);
+        let direction = Vec2::from_angle(turn).rotate(heading).extend(0.);
+        velocity.change_direction(direction);
+        transform.rotation = rotation_from_direction(direction);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn detonate_bombs(
+    mut commands: Commands,
+    mut bombs: Query<(
+        Entity,
+        &mut Bomb,
+        &Transform,
+        &Collider,
+        Option<&CollisionDamage>,
+        Option<&DamageSource>,
+        Option<&DamageRoll>,
+    )>,
+    enemies: Query<(Entity, &Transform), With<Enemy>>,
+    walls: Query<(), With<Wall>>,
+    mut requests: EventWriter<DamageRequest>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+) {
+    for (entity, mut bomb, transform, collider, contact, source, roll) in bombs.iter_mut() {
+        let impact = collider
+            .collisions
+            .iter()
+            .any(|other| enemies.contains(*other) || walls.contains(*other));
+        if !bomb.fuse.tick(time.delta()).finished() && !impact {
+            continue;
+        }
+
+        let center = transform.translation.truncate();
+        for (enemy, enemy_transform) in enemies.iter() {
+            let distance = enemy_transform.translation.truncate().distance(center);
+            if distance > bomb.radius {
+                continue;
+            }
+            let falloff = 1. - (1. - BOMB_EDGE_DAMAGE) * distance / bomb.radius;
+            requests.send(DamageRequest {
+                target: enemy,
+                amount: ((bomb.damage as f32 * falloff).round() as u32).max(1),
+                // The blast is the same kind of damage as hitting something with the bomb
+                damage_type: contact
+                    .map(|contact| contact.damage_type)
+                    .unwrap_or_default(),
+                source: source.copied().unwrap_or_default(),
+                roll: roll.copied().unwrap_or_default(),
+            });
+        }
+
+        commands.entity(entity).despawn_recursive();
+        commands.spawn((
+            Explosion(Timer::from_seconds(EXPLOSION_DURATION, TimerMode::Once)),
+            MaterialMesh2dBundle {
+                mesh: meshes.add(shape::Circle::new(bomb.radius).into()).into(),
+                material: materials.add(ColorMaterial::from(EXPLOSION_COLOR)),
+                transform: Transform::from_translation(center.extend(150.)),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn pierce_enemies(
+    mut commands: Commands,
+    mut projectiles: Query<(
+        Entity,
+        &mut Piercing,
+        &Collider,
+        Option<&DamageSource>,
+        Option<&DamageRoll>,
+    )>,
+    enemies: Query<(), With<Enemy>>,
+    mut requests: EventWriter<DamageRequest>,
+) {
+    for (entity, mut piercing, collider, source, roll) in projectiles.iter_mut() {
+        for enemy in collider.collisions.iter() {
+            if piercing.hits_left == 0 {
+                break;
+            }
+            if !enemies.contains(*enemy) || !piercing.hit.insert(*enemy) {
+                continue;
+            }
+            piercing.hits_left -= 1;
+            requests.send(DamageRequest {
+                target: *enemy,
+                amount: piercing.damage.amount,
+                damage_type: piercing.damage.damage_type,
+                source: source.copied().unwrap_or_default(),
+                roll: roll.copied().unwrap_or_default(),
+            });
+        }
+        if piercing.hits_left == 0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn fade_explosions(
+    mut commands: Commands,
+    mut explosions: Query<(Entity, &mut Explosion, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+) {
+    for (entity, mut explosion, material) in explosions.iter_mut() {
+        if explosion.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        if let Some(material) = materials.get_mut(material) {
+            material
+                .color
+                .set_a(EXPLOSION_COLOR.a() * (1. - explosion.0.percent()));
+        }
+    }
+}
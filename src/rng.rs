@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::schedule::AppState;
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        // `--seed <n>` on the command line replays a run, like the seeds saved with high scores
+        let seed = std::env::args()
+            .skip_while(|arg| arg != "--seed")
+            .nth(1)
+            .and_then(|seed| seed.parse().ok());
+        app.init_resource::<RunRng>()
+            .insert_resource(RequestedSeed(seed))
+            .add_systems(OnEnter(AppState::InGame), start_run_rng);
+    }
+}
+
+/// The seed the next run starts with, or `None` for a random one.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct RequestedSeed(pub Option<u64>);
+
+/// The random number generator for everything that should be reproducible within a run.
+/// Seeding it with the same value replays the same rolls.
+#[derive(Resource, Debug)]
+pub struct RunRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Default for RunRng {
+    fn default() -> Self {
+        Self::from_seed(rand::random())
+    }
+}
+
+impl RunRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns true with the given probability
+    pub fn chance(&mut self, probability: f32) -> bool {
+        probability > 0.0 && self.rng.gen::<f32>() < probability
+    }
+
+    /// Picks one of `items`, or `None` if there are none
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.rng.gen_range(0..items.len()))
+        }
+    }
+
+    /// Returns a random angle in radians
+    pub fn angle(&mut self) -> f32 {
+        self.rng.gen_range(0.0..std::f32::consts::TAU)
+    }
+
+    /// Returns a multiplier in `1.0 - variance ..= 1.0 + variance`
+    pub fn variance(&mut self, variance: f32) -> f32 {
+        if variance > 0.0 {
+            self.rng.gen_range(1.0 - variance..=1.0 + variance)
+        } else {
+            1.0
+        }
+    }
+}
+
+fn start_run_rng(mut rng: ResMut<RunRng>, requested: Res<RequestedSeed>) {
+    *rng = match requested.0 {
+        Some(seed) => RunRng::from_seed(seed),
+        None => RunRng::default(),
+    };
+    info!("Starting run with seed {}", rng.seed());
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn same_seed_same_rolls() {
+        let mut a = RunRng::from_seed(42);
+        let mut b = RunRng::from_seed(42);
+        for _ in 0..100 {
+            assert_eq!(a.variance(0.3), b.variance(0.3));
+            assert_eq!(a.chance(0.5), b.chance(0.5));
+            assert_eq!(a.angle(), b.angle());
+        }
+    }
+
+    #[test]
+    fn variance_stays_in_range() {
+        let mut rng = RunRng::from_seed(7);
+        for _ in 0..100 {
+            let multiplier = rng.variance(0.2);
+            assert!((0.8..=1.2).contains(&multiplier));
+        }
+        assert_eq!(rng.variance(0.0), 1.0);
+    }
+
+    #[test]
+    fn requested_seed_starts_the_run() {
+        let mut world = World::new();
+        world.insert_resource(RunRng::from_seed(1));
+        world.insert_resource(RequestedSeed(Some(1234)));
+        world.run_system_once(start_run_rng);
+        assert_eq!(world.resource::<RunRng>().seed(), 1234);
+    }
+}
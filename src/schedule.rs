@@ -5,6 +5,8 @@ pub struct SchedulePlugin;
 impl Plugin for SchedulePlugin {
     fn build(&self, app: &mut App) {
         app.add_state::<AppState>()
+            .add_state::<PauseState>()
+            .add_state::<OptionsState>()
             .configure_sets(
                 Update,
                 (
@@ -14,14 +16,14 @@ impl Plugin for SchedulePlugin {
                     InGame::CollisionDetection,
                 )
                     .chain()
-                    .run_if(in_state(AppState::InGame)),
+                    .run_if(in_state(AppState::InGame).and_then(in_state(PauseState::Running))),
             )
             .add_systems(
                 Update,
                 apply_deferred
                     .before(InGame::UserInput)
                     .after(InGame::ProcessCombat)
-                    .run_if(in_state(AppState::InGame)),
+                    .run_if(in_state(AppState::InGame).and_then(in_state(PauseState::Running))),
             );
     }
 }
@@ -40,4 +42,24 @@ pub enum AppState {
     Menu,
     InGame,
     GameOver,
+    /// The high score table, reached from the main menu
+    HighScores,
+}
+
+/// Whether gameplay is paused. Only meaningful while in [`AppState::InGame`].
+#[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// Whether the options screen is open on top of the main or pause menu.
+#[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum OptionsState {
+    #[default]
+    Closed,
+    Open,
+    /// The controls screen, reached from the options screen
+    Controls,
 }
@@ -0,0 +1,228 @@
+use std::{fs, path::PathBuf};
+
+use bevy::{
+    audio::GlobalVolume,
+    prelude::*,
+    window::{PrimaryWindow, WindowMode, WindowResolution},
+};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aim::AimSettings,
+    camera::{CameraZoom, ScreenEffects},
+    flash::FlashSettings,
+    health::DamageNumbers,
+    input::InputBindings,
+};
+
+const SETTINGS_FILE: &str = "settings.ron";
+/// Window sizes offered on the options screen
+pub const RESOLUTIONS: [(u32, u32); 5] = [
+    (800, 600),
+    (1024, 768),
+    (1280, 720),
+    (1600, 900),
+    (1920, 1080),
+];
+
+pub struct SettingsPlugin {
+    /// Why the saved settings couldn't be read, reported once logging is up
+    pub load_error: Option<String>,
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(error) = &self.load_error {
+            warn!("Could not read settings, using the defaults: {error}");
+        }
+        // `main` normally inserts the settings it configured the window with
+        app.init_resource::<Settings>().add_systems(
+            Update,
+            (apply_settings, save_settings).run_if(resource_changed::<Settings>()),
+        );
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "WINDOWED",
+            DisplayMode::Borderless => "BORDERLESS",
+            DisplayMode::Fullscreen => "FULLSCREEN",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            DisplayMode::Borderless => DisplayMode::Fullscreen,
+            DisplayMode::Fullscreen => DisplayMode::Windowed,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        self.next().next()
+    }
+}
+
+impl From<DisplayMode> for WindowMode {
+    fn from(value: DisplayMode) -> Self {
+        match value {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+/// Everything the player can change on the options screen, saved between sessions.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Volumes are from 0.0 to 1.0
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub display_mode: DisplayMode,
+    pub resolution: (u32, u32),
+    pub zoom: u32,
+    pub damage_numbers: bool,
+    /// From 0.0 (off) to 1.0
+    pub screen_shake: f32,
+    /// Briefly freezes the game on big impacts
+    pub hit_stop: bool,
+    /// Turns off hit flashes and blinking
+    pub reduce_flashing: bool,
+    /// Directional weapons fire towards the cursor or right stick
+    pub mouse_aim: bool,
+    pub bindings: InputBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 0.8,
+            sfx_volume: 0.8,
+            display_mode: DisplayMode::Windowed,
+            resolution: RESOLUTIONS[0],
+            zoom: CameraZoom::default().factor,
+            damage_numbers: true,
+            screen_shake: 1.0,
+            hit_stop: true,
+            reduce_flashing: false,
+            mouse_aim: false,
+            bindings: InputBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "Bevy Survivors")
+            .map(|dirs| dirs.config_dir().join(SETTINGS_FILE))
+    }
+
+    /// Reads the saved settings, or the defaults if there are none. Runs before the app exists,
+    /// so a file that can't be read is returned as an error for [`SettingsPlugin`] to report.
+    pub fn load() -> Result<Self, String> {
+        let Some(contents) = Self::path().and_then(|path| fs::read_to_string(path).ok()) else {
+            return Ok(Self::default());
+        };
+        ron::from_str(&contents).map_err(|error| error.to_string())
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("No config directory available, settings won't be saved");
+            return;
+        };
+        let result = ron::ser::to_string_pretty(self, Default::default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+                }
+                fs::write(&path, contents).map_err(|error| error.to_string())
+            });
+        if let Err(error) = result {
+            error!("Could not save settings to {}: {error}", path.display());
+        }
+    }
+
+    /// The primary window as these settings describe it
+    pub fn window(&self) -> Window {
+        let (width, height) = self.resolution;
+        Window {
+            title: "Bevy Survivors".into(),
+            resolution: WindowResolution::new(width as f32, height as f32),
+            mode: self.display_mode.into(),
+            resizable: true,
+            resize_constraints: WindowResizeConstraints {
+                min_width: 400.,
+                min_height: 300.,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Pushes the settings out to the resources and window they control.
+/// Music and sound effect volumes are stored for when the game has audio to play.
+#[allow(clippy::too_many_arguments)]
+fn apply_settings(
+    settings: Res<Settings>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+    mut zoom: ResMut<CameraZoom>,
+    mut screen_effects: ResMut<ScreenEffects>,
+    mut damage_numbers: ResMut<DamageNumbers>,
+    mut flashes: ResMut<FlashSettings>,
+    mut aim: ResMut<AimSettings>,
+    mut volume: ResMut<GlobalVolume>,
+    mut applied_window: Local<Option<(DisplayMode, (u32, u32))>>,
+) {
+    // Only touched when those settings change, so a window the player resized stays that size
+    let wanted_window = Some((settings.display_mode, settings.resolution));
+    let window = window
+        .get_single_mut()
+        .ok()
+        .filter(|_| *applied_window != wanted_window);
+    if let Some(mut window) = window {
+        *applied_window = wanted_window;
+        let mode = settings.display_mode.into();
+        if window.mode != mode {
+            window.mode = mode;
+        }
+        let (width, height) = settings.resolution;
+        if settings.display_mode == DisplayMode::Windowed
+            && (window.resolution.width() as u32 != width
+                || window.resolution.height() as u32 != height)
+        {
+            window.resolution.set(width as f32, height as f32);
+        }
+    }
+    zoom.factor = settings.zoom;
+    screen_effects.screen_shake = settings.screen_shake;
+    screen_effects.hit_stop = settings.hit_stop;
+    damage_numbers.enabled = settings.damage_numbers;
+    flashes.enabled = !settings.reduce_flashing;
+    aim.enabled = settings.mouse_aim;
+    *volume = GlobalVolume::new(settings.master_volume);
+}
+
+fn save_settings(settings: Res<Settings>) {
+    // Freshly inserted settings were just loaded, there's nothing new to save
+    if !settings.is_added() {
+        settings.save();
+    }
+}
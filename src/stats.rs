@@ -0,0 +1,151 @@
+use bevy::{prelude::*, time::Stopwatch, utils::HashMap};
+
+use crate::{
+    damage::{DamagePipeline, DeathEvent},
+    enemies::EnemyKind,
+    experience::LevelUpEvent,
+    health::DamageEvent,
+    pickups::PickupCollected,
+    player::{Player, Weapon, WeaponType},
+    schedule::{AppState, InGame},
+};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_systems(OnEnter(AppState::InGame), reset_run_stats)
+            .add_systems(
+                Update,
+                (count_kills, count_damage)
+                    .after(DamagePipeline::Apply)
+                    .in_set(InGame::ProcessCombat),
+            )
+            .add_systems(
+                Update,
+                (
+                    tick_run_time,
+                    track_equipped_weapons,
+                    count_levels,
+                    count_pickups,
+                )
+                    .in_set(InGame::EntityUpdates),
+            );
+    }
+}
+
+/// Damage dealt by one weapon over the run.
+#[derive(Debug, Clone, Default)]
+pub struct WeaponStats {
+    /// Run time in seconds when the weapon was equipped
+    pub equipped_at: f32,
+    pub damage: u32,
+    pub hits: u32,
+}
+
+impl WeaponStats {
+    /// Average damage per second since the weapon was equipped, up to `now`
+    pub fn dps(&self, now: f32) -> f32 {
+        let held = now - self.equipped_at;
+        if held > 0. {
+            self.damage as f32 / held
+        } else {
+            0.
+        }
+    }
+}
+
+/// What has happened so far in the current run.
+#[derive(Resource, Debug)]
+pub struct RunStats {
+    pub time: Stopwatch,
+    pub level: u32,
+    pub kills: u32,
+    /// Kills keyed by [`EnemyKind`]
+    pub kills_by_type: HashMap<String, u32>,
+    pub weapons: HashMap<WeaponType, WeaponStats>,
+    pub damage_taken: u32,
+    pub pickups: u32,
+}
+
+impl Default for RunStats {
+    fn default() -> Self {
+        Self {
+            time: Stopwatch::new(),
+            level: 1,
+            kills: 0,
+            kills_by_type: HashMap::new(),
+            weapons: HashMap::new(),
+            damage_taken: 0,
+            pickups: 0,
+        }
+    }
+}
+
+fn reset_run_stats(mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+fn tick_run_time(mut stats: ResMut<RunStats>, time: Res<Time>) {
+    stats.time.tick(time.delta());
+}
+
+fn count_kills(
+    mut deaths: EventReader<DeathEvent>,
+    mut stats: ResMut<RunStats>,
+    enemies: Query<&EnemyKind>,
+) {
+    for death in deaths.read() {
+        let Ok(kind) = enemies.get(death.entity) else {
+            continue;
+        };
+        stats.kills += 1;
+        *stats.kills_by_type.entry(kind.0.clone()).or_default() += 1;
+    }
+}
+
+fn count_damage(
+    mut events: EventReader<DamageEvent>,
+    mut stats: ResMut<RunStats>,
+    players: Query<(), With<Player>>,
+) {
+    for event in events.read() {
+        if players.contains(event.receiver) {
+            stats.damage_taken += event.amount;
+        } else if let Some(weapon) = event.source.weapon {
+            let now = stats.time.elapsed_secs();
+            let weapon = stats.weapons.entry(weapon).or_insert(WeaponStats {
+                equipped_at: now,
+                ..Default::default()
+            });
+            weapon.damage += event.amount + event.absorbed;
+            weapon.hits += 1;
+        }
+    }
+}
+
+fn track_equipped_weapons(mut stats: ResMut<RunStats>, weapons: Query<&Weapon, With<Player>>) {
+    for weapon in weapons.iter() {
+        if !stats.weapons.contains_key(&weapon.kind()) {
+            let now = stats.time.elapsed_secs();
+            stats.weapons.insert(
+                weapon.kind(),
+                WeaponStats {
+                    equipped_at: now,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+fn count_levels(mut level_ups: EventReader<LevelUpEvent>, mut stats: ResMut<RunStats>) {
+    for level_up in level_ups.read() {
+        stats.level = stats.level.max(level_up.level);
+    }
+}
+
+fn count_pickups(mut collected: EventReader<PickupCollected>, mut stats: ResMut<RunStats>) {
+    stats.pickups += collected.read().count() as u32;
+}
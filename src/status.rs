@@ -0,0 +1,286 @@
+use bevy::{ecs::system::Command, prelude::*};
+
+use crate::{
+    collision::CollisionEvent,
+    damage::{DamageRequest, DamageSource, DamageType, Vulnerability},
+    enemies::Enemy,
+    health::HealEvent,
+    movement::SpeedModifier,
+    schedule::InGame,
+};
+
+/// How many separate poison applications can be running on one entity at a time
+const MAX_POISON_STACKS: usize = 5;
+/// Damage multiplier for hits against frozen entities
+const FROZEN_VULNERABILITY: f32 = 1.5;
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_status_on_hit.in_set(InGame::ProcessCombat))
+            .add_systems(
+                Update,
+                (
+                    tick_status_effects,
+                    update_status_modifiers,
+                    tint_affected_sprites,
+                )
+                    .chain()
+                    .in_set(InGame::EntityUpdates),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusEffectKind {
+    /// Reduces movement speed by `magnitude` (0.0 - 1.0)
+    Slow,
+    /// Deals `magnitude` damage every tick, refreshed and intensified by new applications
+    Burn,
+    /// Deals `magnitude` damage every tick, each application stacks separately
+    Poison,
+    /// Stops all movement and steering
+    Freeze,
+    /// Restores `magnitude` health every tick, each application stacks separately
+    Heal,
+}
+
+impl StatusEffectKind {
+    pub fn color(&self) -> Color {
+        match self {
+            StatusEffectKind::Slow => Color::rgb(0.6, 0.6, 1.0),
+            StatusEffectKind::Burn => Color::ORANGE,
+            StatusEffectKind::Poison => Color::LIME_GREEN,
+            StatusEffectKind::Freeze => Color::CYAN,
+            StatusEffectKind::Heal => Color::GREEN,
+        }
+    }
+
+    /// The type of the periodic damage dealt by this effect, if it deals any
+    pub fn damage_type(&self) -> Option<DamageType> {
+        match self {
+            StatusEffectKind::Burn => Some(DamageType::Fire),
+            StatusEffectKind::Poison => Some(DamageType::Poison),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    pub magnitude: f32,
+    pub duration: Timer,
+    pub tick: Timer,
+    pub source: DamageSource,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusEffectKind, magnitude: f32, duration: f32, tick_interval: f32) -> Self {
+        Self {
+            kind,
+            magnitude,
+            duration: Timer::from_seconds(duration, TimerMode::Once),
+            tick: Timer::from_seconds(tick_interval, TimerMode::Repeating),
+            source: DamageSource::default(),
+        }
+    }
+
+    pub fn burn(damage: f32, duration: f32) -> Self {
+        Self::new(StatusEffectKind::Burn, damage, duration, 0.5)
+    }
+
+    pub fn poison(damage: f32, duration: f32) -> Self {
+        Self::new(StatusEffectKind::Poison, damage, duration, 1.0)
+    }
+
+    /// Reduces movement speed by `amount` (0.0 - 1.0) for `duration` seconds
+    pub fn slow(amount: f32, duration: f32) -> Self {
+        Self::new(StatusEffectKind::Slow, amount, duration, duration)
+    }
+
+    pub fn freeze(duration: f32) -> Self {
+        Self::new(StatusEffectKind::Freeze, 1.0, duration, duration)
+    }
+
+    /// Restores `amount` health one point at a time over `duration` seconds
+    pub fn heal(amount: u32, duration: f32) -> Self {
+        Self::new(
+            StatusEffectKind::Heal,
+            1.0,
+            duration,
+            duration / amount.max(1) as f32,
+        )
+    }
+}
+
+/// The status effects currently active on an entity.
+#[derive(Component, Debug, Default, Clone)]
+pub struct StatusEffects(Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn has(&self, kind: StatusEffectKind) -> bool {
+        self.0.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// Adds an effect following the stacking rules of its kind
+    pub fn apply(&mut self, effect: StatusEffect) {
+        let existing = self.0.iter_mut().find(|e| e.kind == effect.kind);
+        match (effect.kind, existing) {
+            // Slow and freeze don't stack, the strongest one wins and the duration is refreshed
+            (StatusEffectKind::Slow | StatusEffectKind::Freeze, Some(existing)) => {
+                existing.magnitude = existing.magnitude.max(effect.magnitude);
+                existing.duration = effect.duration;
+                existing.source = effect.source;
+            }
+            // Burns add up while refreshing the duration
+            (StatusEffectKind::Burn, Some(existing)) => {
+                existing.magnitude += effect.magnitude;
+                existing.duration = effect.duration;
+                existing.source = effect.source;
+            }
+            // Poison stacks independently, replacing the oldest stack when full
+            (StatusEffectKind::Poison, Some(_)) => {
+                let stacks = self.0.iter().filter(|e| e.kind == effect.kind).count();
+                if stacks >= MAX_POISON_STACKS {
+                    if let Some(oldest) = self.0.iter().position(|e| e.kind == effect.kind) {
+                        self.0.remove(oldest);
+                    }
+                }
+                self.0.push(effect);
+            }
+            (StatusEffectKind::Heal, Some(_)) | (_, None) => self.0.push(effect),
+        }
+    }
+
+    /// The combined multiplier to apply to movement speed
+    pub fn speed_multiplier(&self) -> f32 {
+        self.0
+            .iter()
+            .fold(1.0, |multiplier, effect| match effect.kind {
+                StatusEffectKind::Slow => multiplier * (1.0 - effect.magnitude.clamp(0.0, 1.0)),
+                StatusEffectKind::Freeze => 0.0,
+                _ => multiplier,
+            })
+    }
+
+    /// The tint of the most recently applied effect, if any
+    pub fn tint(&self) -> Option<Color> {
+        self.0.last().map(|effect| effect.kind.color())
+    }
+}
+
+/// Inflicts a status effect on whatever the entity hits.
+#[derive(Component, Debug, Clone)]
+pub struct InflictsStatus(pub StatusEffect);
+
+/// Applies a status effect to an entity, adding [`StatusEffects`] if it doesn't have any yet.
+pub struct ApplyStatus {
+    pub entity: Entity,
+    pub effect: StatusEffect,
+}
+
+impl Command for ApplyStatus {
+    fn apply(self, world: &mut World) {
+        let Some(mut entity) = world.get_entity_mut(self.entity) else {
+            return;
+        };
+        if let Some(mut effects) = entity.get_mut::<StatusEffects>() {
+            effects.apply(self.effect);
+        } else {
+            let mut effects = StatusEffects::default();
+            effects.apply(self.effect);
+            entity.insert(effects);
+        }
+    }
+}
+
+/// Marker for entities that are frozen in place and can't steer.
+#[derive(Component, Debug, Default)]
+pub struct Frozen;
+
+fn apply_status_on_hit(
+    mut commands: Commands,
+    mut events: EventReader<CollisionEvent>,
+    mut receivers: Query<Option<&mut StatusEffects>, With<Enemy>>,
+    inflicters: Query<(&InflictsStatus, Option<&DamageSource>)>,
+) {
+    for collision in events.read() {
+        let Ok(effects) = receivers.get_mut(collision.entity) else {
+            continue;
+        };
+
+        let Ok((InflictsStatus(effect), source)) = inflicters.get(collision.collided_with) else {
+            continue;
+        };
+
+        let mut effect = effect.clone();
+        if let Some(source) = source {
+            effect.source = *source;
+        }
+
+        if let Some(mut effects) = effects {
+            effects.apply(effect);
+        } else {
+            let mut effects = StatusEffects::default();
+            effects.apply(effect);
+            commands.entity(collision.entity).insert(effects);
+        }
+    }
+}
+
+fn tick_status_effects(
+    mut query: Query<(Entity, &mut StatusEffects)>,
+    mut damage_requests: EventWriter<DamageRequest>,
+    mut heal_events: EventWriter<HealEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut effects) in query.iter_mut() {
+        for effect in effects.0.iter_mut() {
+            effect.duration.tick(time.delta());
+            effect.tick.tick(time.delta());
+            for _ in 0..effect.tick.times_finished_this_tick() {
+                let amount = effect.magnitude.round() as u32;
+                if effect.kind == StatusEffectKind::Heal {
+                    heal_events.send(HealEvent {
+                        target: entity,
+                        amount,
+                    });
+                } else if let Some(damage_type) = effect.kind.damage_type() {
+                    damage_requests.send(DamageRequest {
+                        target: entity,
+                        amount,
+                        damage_type,
+                        source: effect.source,
+                        roll: Default::default(),
+                    });
+                }
+            }
+        }
+        effects.0.retain(|effect| !effect.duration.finished());
+    }
+}
+
+fn update_status_modifiers(
+    mut commands: Commands,
+    query: Query<(Entity, &StatusEffects), Changed<StatusEffects>>,
+) {
+    for (entity, effects) in query.iter() {
+        let mut entity = commands.entity(entity);
+        entity.insert(SpeedModifier(effects.speed_multiplier()));
+        if effects.has(StatusEffectKind::Freeze) {
+            entity.insert((Frozen, Vulnerability(FROZEN_VULNERABILITY)));
+        } else {
+            entity.remove::<(Frozen, Vulnerability)>();
+        }
+    }
+}
+
+pub fn tint_affected_sprites(
+    mut query: Query<(&StatusEffects, &mut TextureAtlasSprite), Changed<StatusEffects>>,
+) {
+    for (effects, mut sprite) in query.iter_mut() {
+        sprite.color = effects.tint().unwrap_or(Color::WHITE);
+    }
+}